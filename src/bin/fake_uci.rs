@@ -0,0 +1,78 @@
+use std::io::{self, BufRead};
+use std::process;
+use std::thread;
+use std::time::Duration;
+use chess::board::{Move, Position};
+
+//a scripted stand-in for a real UCI engine, for trying the engine client
+//without one installed. It always plays the first legal move it finds.
+//
+//  --delay <ms>        think this long before every bestmove
+//  --crash-after <n>   exit without a word on the (n+1)th "go"
+//  --hang              never answer "go"
+//  --illegal           answer "go" with a move that is not legal
+
+fn main(){
+    let args:Vec<String> = std::env::args().skip(1).collect();
+    let value = |flag:&str| args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i+1))
+        .and_then(|value| value.parse::<u64>().ok());
+    let delay = Duration::from_millis(value("--delay").unwrap_or(0));
+    let crash_after = value("--crash-after");
+    let hang = args.iter().any(|arg| arg == "--hang");
+    let illegal = args.iter().any(|arg| arg == "--illegal");
+
+    let mut position = Position::startpos();
+    let mut searches = 0;
+    for line in io::stdin().lock().lines(){
+        let line = match line{
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let words:Vec<&str> = line.split_whitespace().collect();
+        match words.first().copied(){
+            Some("uci") => {
+                println!("id name fake engine");
+                println!("id author nobody");
+                println!("option name Hash type spin default 16 min 1 max 1024");
+                println!("option name Skill Level type spin default 20 min 0 max 20");
+                println!("option name Ponder type check default false");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("position") => position = parse_position(&words[1..]),
+            Some("go") => {
+                if crash_after.is_some_and(|n| searches >= n){
+                    process::exit(3);
+                }
+                searches+=1;
+                if hang{
+                    continue
+                }
+                thread::sleep(delay);
+                let mv = if illegal {"a1a1".to_string()}
+                    else {position.legal_moves().first().map_or("0000".to_string(),|mv| mv.to_string())};
+                println!("bestmove {}",mv);
+            }
+            Some("quit") => break,
+            _ => {}
+        }
+    }
+}
+
+//the same "position" command the real engines take; mistakes give the start position
+fn parse_position(words:&[&str])->Position{
+    let moves_at = words.iter().position(|word| *word == "moves").unwrap_or(words.len());
+    let mut position = match words.first().copied(){
+        Some("fen") => Position::from_fen(&words[1..moves_at].join(" ")).unwrap_or_default(),
+        _ => Position::startpos(),
+    };
+    for text in words.iter().skip(moves_at+1){
+        match Move::from_uci(text){
+            Some(mv) => position.play(&mv),
+            None => break,
+        }
+    }
+    position
+}
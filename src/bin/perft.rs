@@ -0,0 +1,55 @@
+use std::time::Instant;
+use chess::board::{Move, Position};
+use chess::perft;
+
+//perft <depth> [fen <fen>] [moves <move>...]
+//prints the node count below every legal move, then the total
+
+fn main(){
+    let args:Vec<String> = std::env::args().skip(1).collect();
+    let depth = match args.first().and_then(|arg| arg.parse::<u32>().ok()){
+        Some(depth) => depth,
+        None => {
+            eprintln!("usage: perft <depth> [fen <fen>] [moves <move>...]");
+            std::process::exit(2);
+        }
+    };
+    let position = match parse_position(&args[1..]){
+        Ok(position) => position,
+        Err(error) => {
+            eprintln!("{}",error);
+            std::process::exit(2);
+        }
+    };
+
+    let start = Instant::now();
+    let mut divide = perft::divide(&position,depth);
+    divide.sort_by_key(|(mv,_)| mv.to_string());
+    let mut total = 0;
+    for (mv,nodes) in divide.iter(){
+        println!("{}: {}",mv,nodes);
+        total+=nodes;
+    }
+    if depth == 0{
+        total = 1;
+    }
+    let elapsed = start.elapsed();
+    println!();
+    println!("Nodes searched: {}",total);
+    println!("Time: {:.3}s ({:.0} nodes/s)",elapsed.as_secs_f64(),total as f64/elapsed.as_secs_f64().max(1e-9));
+}
+
+fn parse_position(args:&[String])->Result<Position,String>{
+    let moves_at = args.iter().position(|arg| arg == "moves").unwrap_or(args.len());
+    let mut position = match args.first().map(|arg| arg.as_str()){
+        Some("fen") => Position::from_fen(&args[1..moves_at].join(" ")).map_err(|e| format!("bad FEN: {}",e))?,
+        Some("startpos") | Some("moves") | None => Position::startpos(),
+        Some(other) => return Err(format!("expected fen or moves, got '{}'",other)),
+    };
+    for text in args.iter().skip(moves_at+1){
+        let mv = Move::from_uci(text).filter(|mv| position.is_legal(mv))
+            .ok_or(format!("illegal move {}",text))?;
+        position.play(&mv);
+    }
+    Ok(position)
+}
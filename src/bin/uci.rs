@@ -0,0 +1,190 @@
+use std::io::{self, BufRead};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use chess::board::{Color, Move, Position};
+use chess::search::{self, Limits, SearchInfo};
+use chess::syzygy::Tablebase;
+
+//the engine behind a UCI front end: reads commands from stdin, answers on stdout,
+//searches on a thread of its own so "stop" can interrupt it
+
+fn main(){
+    let mut position = Position::startpos();
+    let mut running:Option<(Arc<AtomicBool>,JoinHandle<()>)> = None;
+    let mut tablebase:Option<Arc<Tablebase>> = None;
+
+    for line in io::stdin().lock().lines(){
+        let line = match line{
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let words:Vec<&str> = line.split_whitespace().collect();
+        match words.first().copied(){
+            Some("uci") => {
+                println!("id name {} {}",env!("CARGO_PKG_NAME"),env!("CARGO_PKG_VERSION"));
+                println!("id author the {} developers",env!("CARGO_PKG_NAME"));
+                println!("option name SyzygyPath type string default <empty>");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("setoption") => match parse_option(&words[1..]){
+                Some((name,value)) if name.eq_ignore_ascii_case("SyzygyPath") => {
+                    finish(&mut running);
+                    tablebase = None;
+                    if !value.is_empty() && value != "<empty>"{
+                        match Tablebase::open(&value){
+                            Ok(opened) => {
+                                println!("info string found {} tablebases, up to {} pieces",opened.len(),opened.max_pieces());
+                                tablebase = Some(Arc::new(opened));
+                            }
+                            Err(error) => println!("info string {}",error),
+                        }
+                    }
+                }
+                Some((name,_)) => println!("info string no option {}",name),
+                None => println!("info string setoption name <name> [value <value>]"),
+            },
+            Some("ucinewgame") => {
+                finish(&mut running);
+                position = Position::startpos();
+            }
+            Some("position") => match parse_position(&words[1..]){
+                Ok((parsed,warning)) => {
+                    position = parsed;
+                    if let Some(warning) = warning{
+                        println!("info string {}",warning);
+                    }
+                }
+                Err(error) => println!("info string {}",error),
+            },
+            Some("go") => {
+                finish(&mut running);
+                let limits = parse_go(&words[1..],position.side_to_move());
+                let infinite = words.contains(&"infinite");
+                let stop = Arc::new(AtomicBool::new(false));
+                let thread_stop = stop.clone();
+                let searched = position;
+                let thread_tablebase = tablebase.clone();
+                let handle = thread::spawn(move || {
+                    let tablebase = thread_tablebase.as_deref();
+                    if let Some(tablebase) = tablebase{
+                        report_tablebase(tablebase,&searched);
+                    }
+                    let info = search::search_with(&searched,limits,&thread_stop,tablebase,report);
+                    //an infinite search reports its move only when told to stop,
+                    //even if it ran out of depth or found a mate before
+                    while infinite && !thread_stop.load(Ordering::Relaxed){
+                        thread::sleep(Duration::from_millis(5));
+                    }
+                    match info.best_move(){
+                        Some(mv) => println!("bestmove {}",mv),
+                        //mated or stalemated, UCI wants a null move then
+                        None => println!("bestmove 0000"),
+                    }
+                });
+                running = Some((stop,handle));
+            }
+            Some("stop") => finish(&mut running),
+            Some("quit") => {
+                finish(&mut running);
+                break
+            }
+            //unknown commands are ignored, as the protocol asks
+            _ => {}
+        }
+    }
+}
+
+//stops the search if there is one and waits for its bestmove
+fn finish(running:&mut Option<(Arc<AtomicBool>,JoinHandle<()>)>){
+    if let Some((stop,handle)) = running.take(){
+        stop.store(true,Ordering::Relaxed);
+        let _ = handle.join();
+    }
+}
+
+fn report(info:&SearchInfo){
+    let score = match info.mate_in(){
+        Some(moves) => format!("mate {}",moves),
+        None => format!("cp {}",info.score),
+    };
+    let millis = info.time.as_millis().max(1);
+    let pv:Vec<String> = info.pv.iter().map(|mv| mv.to_string()).collect();
+    println!("info depth {} score {} nodes {} nps {} tbhits {} time {} pv {}",
+        info.depth,score,info.nodes,info.nodes as u128*1000/millis,info.tbhits,millis,pv.join(" "));
+}
+
+//the tablebase verdict on the position searched, when there is one
+fn report_tablebase(tablebase:&Tablebase,position:&Position){
+    if let Some(wdl) = tablebase.probe_wdl(position){
+        match tablebase.probe_dtz(position){
+            Some(dtz) => println!("info string tablebase {} dtz {}",wdl.name(),dtz),
+            None => println!("info string tablebase {}",wdl.name()),
+        }
+    }
+}
+
+//setoption name <name> [value <value>]; both may have spaces in them
+fn parse_option(words:&[&str])->Option<(String,String)>{
+    if words.first() != Some(&"name"){
+        return None
+    }
+    let value_at = words.iter().position(|word| *word == "value").unwrap_or(words.len());
+    let name = words[1..value_at].join(" ");
+    let value = words.get(value_at+1..).map(|rest| rest.join(" ")).unwrap_or_default();
+    if name.is_empty() {None} else {Some((name,value))}
+}
+
+//position [startpos | fen <fen>] [moves <move>...]; like other engines it
+//plays the moves up to a bad one and says so, rather than keep the old position
+fn parse_position(words:&[&str])->Result<(Position,Option<String>),String>{
+    let moves_at = words.iter().position(|word| *word == "moves").unwrap_or(words.len());
+    let mut position = match words.first().copied(){
+        Some("startpos") => Position::startpos(),
+        Some("fen") => Position::from_fen(&words[1..moves_at].join(" "))?,
+        _ => return Err("position needs startpos or fen".to_string()),
+    };
+    for text in words.iter().skip(moves_at+1){
+        match Move::from_uci(text).filter(|mv| position.is_legal(mv)){
+            Some(mv) => position.play(&mv),
+            None => return Ok((position,Some(format!("illegal move {}, the moves stop before it",text)))),
+        }
+    }
+    Ok((position,None))
+}
+
+//go [depth n] [movetime ms] [nodes n] [wtime ms] [btime ms] [winc ms] [binc ms]
+//[movestogo n] [infinite]; without any limit it searches until "stop"
+fn parse_go(words:&[&str],side:Color)->Limits{
+    let mut limits = Limits{depth:u32::MAX,movetime:None,nodes:None};
+    let mut clock = None;
+    let mut increment = Duration::ZERO;
+    let mut moves_to_go = None;
+    let (time_key,increment_key) = match side{
+        Color::White => ("wtime","winc"),
+        Color::Black => ("btime","binc"),
+    };
+    let mut i = 0;
+    while i < words.len(){
+        let value = words.get(i+1).and_then(|word| word.parse::<u64>().ok());
+        match (words[i],value){
+            ("depth",Some(depth)) => limits.depth = depth as u32,
+            ("movetime",Some(millis)) => limits.movetime = Some(Duration::from_millis(millis)),
+            ("nodes",Some(nodes)) => limits.nodes = Some(nodes),
+            ("movestogo",Some(moves)) => moves_to_go = Some(moves as u32),
+            (key,Some(millis)) if key == time_key => clock = Some(Duration::from_millis(millis)),
+            (key,Some(millis)) if key == increment_key => increment = Duration::from_millis(millis),
+            _ => {
+                i+=1;
+                continue
+            }
+        }
+        i+=2;
+    }
+    if let (Some(remaining),None) = (clock,limits.movetime){
+        limits.movetime = Some(search::time_for_move(remaining,increment,moves_to_go));
+    }
+    limits
+}
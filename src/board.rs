@@ -0,0 +1,798 @@
+use std::fmt;
+use crate::zobrist;
+
+//headless chess rules: no bevy in here, so tools, tests and bots can use them directly
+
+#[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
+pub enum Color{
+    White,
+    Black,
+}
+
+impl Color{
+    pub fn opposite(&self)->Color{
+        match self{
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+
+    //0 for white, 1 for black
+    pub fn index(&self)->usize{
+        match self{
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
+pub enum PieceKind{
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+impl PieceKind{
+    pub const ALL:[PieceKind;6] = [
+        PieceKind::Pawn,
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+        PieceKind::Queen,
+        PieceKind::King,
+    ];
+
+    //what a pawn may turn into, best first
+    pub const PROMOTIONS:[PieceKind;4] = [
+        PieceKind::Queen,
+        PieceKind::Rook,
+        PieceKind::Bishop,
+        PieceKind::Knight,
+    ];
+
+    pub fn name(&self)->&'static str{
+        match self{
+            PieceKind::Pawn => "pawn",
+            PieceKind::Knight => "knight",
+            PieceKind::Bishop => "bishop",
+            PieceKind::Rook => "rook",
+            PieceKind::Queen => "queen",
+            PieceKind::King => "king",
+        }
+    }
+
+    pub fn from_name(name:&str)->Option<PieceKind>{
+        PieceKind::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+
+    //lowercase letter as used in FEN and UCI promotions ("n", "q", ...)
+    pub fn letter(&self)->char{
+        match self{
+            PieceKind::Pawn => 'p',
+            PieceKind::Knight => 'n',
+            PieceKind::Bishop => 'b',
+            PieceKind::Rook => 'r',
+            PieceKind::Queen => 'q',
+            PieceKind::King => 'k',
+        }
+    }
+
+    pub fn from_letter(letter:char)->Option<PieceKind>{
+        let letter = letter.to_ascii_lowercase();
+        PieceKind::ALL.iter().copied().find(|kind| kind.letter() == letter)
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
+pub struct Piece{
+    pub kind:PieceKind,
+    pub color:Color,
+}
+
+impl Piece{
+    pub fn new(kind:PieceKind,color:Color)->Piece{
+        Piece{kind,color}
+    }
+}
+
+//0..64, a1 = 0, b1 = 1, ..., h8 = 63
+#[derive(Clone,Copy,PartialEq,Eq,Hash,Debug,PartialOrd,Ord)]
+pub struct Square(u8);
+
+impl Square{
+    pub fn new(file:u8,rank:u8)->Square{
+        debug_assert!(file < 8 && rank < 8);
+        Square(rank*8+file)
+    }
+
+    pub fn from_index(index:usize)->Square{
+        debug_assert!(index < 64);
+        Square(index as u8)
+    }
+
+    //None when the step leaves the board
+    pub fn offset(&self,file_step:i8,rank_step:i8)->Option<Square>{
+        let file = self.file() as i8+file_step;
+        let rank = self.rank() as i8+rank_step;
+        if (0..8).contains(&file) && (0..8).contains(&rank){
+            Some(Square::new(file as u8,rank as u8))
+        }
+        else{
+            None
+        }
+    }
+
+    pub fn file(&self)->u8{
+        self.0 % 8
+    }
+
+    pub fn rank(&self)->u8{
+        self.0 / 8
+    }
+
+    pub fn index(&self)->usize{
+        self.0 as usize
+    }
+
+    pub fn is_light(&self)->bool{
+        (self.file()+self.rank()) % 2 == 1
+    }
+
+    pub fn name(&self)->String{
+        format!("{}{}",(b'a'+self.file()) as char,self.rank()+1)
+    }
+
+    //"e4" -> e4
+    pub fn from_name(name:&str)->Option<Square>{
+        let bytes = name.as_bytes();
+        if bytes.len() != 2{
+            return None
+        }
+        let file = bytes[0].wrapping_sub(b'a');
+        let rank = bytes[1].wrapping_sub(b'1');
+        if file < 8 && rank < 8{
+            Some(Square::new(file,rank))
+        }
+        else{
+            None
+        }
+    }
+
+    pub fn all()->impl Iterator<Item = Square>{
+        (0..64).map(Square)
+    }
+}
+
+impl fmt::Display for Square{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        write!(f,"{}",self.name())
+    }
+}
+
+//castling and en passant are told apart by the position they are played in,
+//so a move is just where from, where to and what a pawn promotes to
+#[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
+pub struct Move{
+    pub from:Square,
+    pub to:Square,
+    pub promotion:Option<PieceKind>,
+}
+
+impl Move{
+    pub fn new(from:Square,to:Square)->Move{
+        Move{from,to,promotion:None}
+    }
+
+    //reads the long algebraic form Display writes: "e2e4", "e7e8q"; castling
+    //is the king's two-square move, "e1g1"
+    pub fn from_uci(text:&str)->Option<Move>{
+        if !text.is_ascii() || (text.len() != 4 && text.len() != 5){
+            return None
+        }
+        let from = Square::from_name(&text[0..2])?;
+        let to = Square::from_name(&text[2..4])?;
+        let promotion = match text.chars().nth(4){
+            Some(letter) => Some(PieceKind::from_letter(letter).filter(|kind| PieceKind::PROMOTIONS.contains(kind))?),
+            None => None,
+        };
+        Some(Move{from,to,promotion})
+    }
+}
+
+//long algebraic, the way UCI writes moves: "e2e4", "e7e8q"
+impl fmt::Display for Move{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        write!(f,"{}{}",self.from,self.to)?;
+        if let Some(kind) = self.promotion{
+            write!(f,"{}",kind.letter())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
+pub struct CastlingRights{
+    pub white_king_side:bool,
+    pub white_queen_side:bool,
+    pub black_king_side:bool,
+    pub black_queen_side:bool,
+}
+
+impl CastlingRights{
+    pub fn all()->CastlingRights{
+        CastlingRights{
+            white_king_side:true,
+            white_queen_side:true,
+            black_king_side:true,
+            black_queen_side:true,
+        }
+    }
+
+    pub fn none()->CastlingRights{
+        CastlingRights{
+            white_king_side:false,
+            white_queen_side:false,
+            black_king_side:false,
+            black_queen_side:false,
+        }
+    }
+
+    pub fn king_side(&self,color:Color)->bool{
+        match color{
+            Color::White => self.white_king_side,
+            Color::Black => self.black_king_side,
+        }
+    }
+
+    pub fn queen_side(&self,color:Color)->bool{
+        match color{
+            Color::White => self.white_queen_side,
+            Color::Black => self.black_queen_side,
+        }
+    }
+
+    //a king or rook leaving (or a rook being taken on) its home square
+    //loses the rights that depend on it
+    fn touch(&mut self,sq:Square){
+        match sq.index(){
+            //e1
+            4 => {
+                self.white_king_side = false;
+                self.white_queen_side = false;
+            }
+            //h1, a1
+            7 => self.white_king_side = false,
+            0 => self.white_queen_side = false,
+            //e8
+            60 => {
+                self.black_king_side = false;
+                self.black_queen_side = false;
+            }
+            //h8, a8
+            63 => self.black_king_side = false,
+            56 => self.black_queen_side = false,
+            _ => {}
+        }
+    }
+}
+
+const KNIGHT_STEPS:[(i8,i8);8] = [(1,2),(2,1),(2,-1),(1,-2),(-1,-2),(-2,-1),(-2,1),(-1,2)];
+const KING_STEPS:[(i8,i8);8] = [(1,0),(1,1),(0,1),(-1,1),(-1,0),(-1,-1),(0,-1),(1,-1)];
+const ROOK_DIRECTIONS:[(i8,i8);4] = [(1,0),(0,1),(-1,0),(0,-1)];
+const BISHOP_DIRECTIONS:[(i8,i8);4] = [(1,1),(-1,1),(-1,-1),(1,-1)];
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug)]
+pub struct Position{
+    board:[Option<Piece>;64],
+    side_to_move:Color,
+    castling:CastlingRights,
+    //square skipped by a double pawn push on the last move
+    en_passant:Option<Square>,
+    halfmove_clock:u32,
+    fullmove_number:u32,
+    //Zobrist key of the pieces, castling rights and side to move, kept up to
+    //date as they change; en passant is added by zobrist()
+    key:u64,
+}
+
+impl Position{
+    pub fn empty()->Position{
+        Position{
+            board:[None;64],
+            side_to_move:Color::White,
+            castling:CastlingRights::none(),
+            en_passant:None,
+            halfmove_clock:0,
+            fullmove_number:1,
+            key:zobrist::turn_key(),
+        }
+    }
+
+    //standard starting position
+    pub fn startpos()->Position{
+        let mut position = Position::empty();
+        let back_rank = [
+            PieceKind::Rook,
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::Queen,
+            PieceKind::King,
+            PieceKind::Bishop,
+            PieceKind::Knight,
+            PieceKind::Rook,
+        ];
+        for file in 0..8{
+            position.set_piece(Square::new(file,0),Some(Piece::new(back_rank[file as usize],Color::White)));
+            position.set_piece(Square::new(file,1),Some(Piece::new(PieceKind::Pawn,Color::White)));
+            position.set_piece(Square::new(file,6),Some(Piece::new(PieceKind::Pawn,Color::Black)));
+            position.set_piece(Square::new(file,7),Some(Piece::new(back_rank[file as usize],Color::Black)));
+        }
+        position.set_castling(CastlingRights::all());
+        position
+    }
+
+    pub fn piece_at(&self,sq:Square)->Option<Piece>{
+        self.board[sq.index()]
+    }
+
+    pub fn set_piece(&mut self,sq:Square,piece:Option<Piece>){
+        if let Some(old) = self.board[sq.index()]{
+            self.key^=zobrist::piece_key(old,sq);
+        }
+        if let Some(new) = piece{
+            self.key^=zobrist::piece_key(new,sq);
+        }
+        self.board[sq.index()] = piece;
+    }
+
+    pub fn side_to_move(&self)->Color{
+        self.side_to_move
+    }
+
+    pub fn set_side_to_move(&mut self,color:Color){
+        if color != self.side_to_move{
+            self.key^=zobrist::turn_key();
+        }
+        self.side_to_move = color;
+    }
+
+    pub fn castling(&self)->CastlingRights{
+        self.castling
+    }
+
+    pub fn set_castling(&mut self,castling:CastlingRights){
+        self.key^=zobrist::castling_key(&self.castling)^zobrist::castling_key(&castling);
+        self.castling = castling;
+    }
+
+    pub fn en_passant(&self)->Option<Square>{
+        self.en_passant
+    }
+
+    pub fn set_en_passant(&mut self,sq:Option<Square>){
+        self.en_passant = sq;
+    }
+
+    pub fn halfmove_clock(&self)->u32{
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self)->u32{
+        self.fullmove_number
+    }
+
+    pub fn set_clocks(&mut self,halfmove_clock:u32,fullmove_number:u32){
+        self.halfmove_clock = halfmove_clock;
+        self.fullmove_number = fullmove_number;
+    }
+
+    //Polyglot compatible position key; the en passant file only counts when
+    //a pawn of the side to move stands next to the pawn that just moved
+    pub fn zobrist(&self)->u64{
+        let mut key = self.key;
+        if let Some(ep) = self.en_passant{
+            let forward = match self.side_to_move{
+                Color::White => 1,
+                Color::Black => -1,
+            };
+            let capturer = Piece::new(PieceKind::Pawn,self.side_to_move);
+            let can_take = [-1,1].iter()
+                .filter_map(|side| ep.offset(*side,-forward))
+                .any(|sq| self.piece_at(sq) == Some(capturer));
+            if can_take{
+                key^=zobrist::en_passant_key(ep.file());
+            }
+        }
+        key
+    }
+
+    //every occupied square with its piece, a1 first
+    pub fn pieces(&self)->impl Iterator<Item = (Square,Piece)> + '_{
+        Square::all().filter_map(move |sq| self.piece_at(sq).map(|piece| (sq,piece)))
+    }
+
+    pub fn king_square(&self,color:Color)->Option<Square>{
+        self.pieces()
+            .find(|(_,piece)| piece.kind == PieceKind::King && piece.color == color)
+            .map(|(sq,_)| sq)
+    }
+
+    //true if any piece of color `by` attacks sq
+    pub fn is_attacked(&self,sq:Square,by:Color)->bool{
+        let is = |target:Option<Square>,kinds:&[PieceKind]| match target.and_then(|t| self.piece_at(t)){
+            Some(piece) => piece.color == by && kinds.contains(&piece.kind),
+            None => false,
+        };
+        //pawns of `by` attack towards the opponent, so look back from sq
+        let pawn_rank = match by{
+            Color::White => -1,
+            Color::Black => 1,
+        };
+        if is(sq.offset(1,pawn_rank),&[PieceKind::Pawn]) || is(sq.offset(-1,pawn_rank),&[PieceKind::Pawn]){
+            return true
+        }
+        if KNIGHT_STEPS.iter().any(|(f,r)| is(sq.offset(*f,*r),&[PieceKind::Knight])){
+            return true
+        }
+        if KING_STEPS.iter().any(|(f,r)| is(sq.offset(*f,*r),&[PieceKind::King])){
+            return true
+        }
+        let sliders = [
+            (ROOK_DIRECTIONS,[PieceKind::Rook,PieceKind::Queen]),
+            (BISHOP_DIRECTIONS,[PieceKind::Bishop,PieceKind::Queen]),
+        ];
+        for (directions,kinds) in sliders.iter(){
+            for (f,r) in directions.iter(){
+                let mut current = sq.offset(*f,*r);
+                while let Some(target) = current{
+                    if let Some(piece) = self.piece_at(target){
+                        if piece.color == by && kinds.contains(&piece.kind){
+                            return true
+                        }
+                        break;
+                    }
+                    current = target.offset(*f,*r);
+                }
+            }
+        }
+        false
+    }
+
+    //is the side to move in check
+    pub fn is_check(&self)->bool{
+        self.is_in_check(self.side_to_move)
+    }
+
+    pub fn is_in_check(&self,color:Color)->bool{
+        match self.king_square(color){
+            Some(sq) => self.is_attacked(sq,color.opposite()),
+            None => false,
+        }
+    }
+
+    //moves that follow the piece movement rules but may leave the own king attacked
+    pub fn pseudo_legal_moves(&self)->Vec<Move>{
+        let mut moves = Vec::with_capacity(64);
+        let us = self.side_to_move;
+        for (from,piece) in self.pieces(){
+            if piece.color != us{
+                continue;
+            }
+            match piece.kind{
+                PieceKind::Pawn => self.pawn_moves(from,&mut moves),
+                PieceKind::Knight => self.step_moves(from,&KNIGHT_STEPS,&mut moves),
+                PieceKind::Bishop => self.slide_moves(from,&BISHOP_DIRECTIONS,&mut moves),
+                PieceKind::Rook => self.slide_moves(from,&ROOK_DIRECTIONS,&mut moves),
+                PieceKind::Queen => {
+                    self.slide_moves(from,&ROOK_DIRECTIONS,&mut moves);
+                    self.slide_moves(from,&BISHOP_DIRECTIONS,&mut moves);
+                }
+                PieceKind::King => {
+                    self.step_moves(from,&KING_STEPS,&mut moves);
+                    self.castling_moves(from,&mut moves);
+                }
+            }
+        }
+        moves
+    }
+
+    pub fn legal_moves(&self)->Vec<Move>{
+        self.pseudo_legal_moves()
+            .into_iter()
+            .filter(|mv| self.is_legal_pseudo(mv))
+            .collect()
+    }
+
+    pub fn legal_moves_from(&self,from:Square)->Vec<Move>{
+        self.legal_moves().into_iter().filter(|mv| mv.from == from).collect()
+    }
+
+    pub fn is_legal(&self,mv:&Move)->bool{
+        self.pseudo_legal_moves().contains(mv) && self.is_legal_pseudo(mv)
+    }
+
+    //a pseudo legal move is legal if our king is not attacked afterwards
+    fn is_legal_pseudo(&self,mv:&Move)->bool{
+        let us = self.side_to_move;
+        let mut after = *self;
+        after.play(mv);
+        !after.is_in_check(us)
+    }
+
+    pub fn has_legal_move(&self)->bool{
+        self.pseudo_legal_moves().iter().any(|mv| self.is_legal_pseudo(mv))
+    }
+
+    pub fn is_checkmate(&self)->bool{
+        self.is_check() && !self.has_legal_move()
+    }
+
+    pub fn is_stalemate(&self)->bool{
+        !self.is_check() && !self.has_legal_move()
+    }
+
+    //dead positions: king against king, a lone minor piece, or bishops that
+    //all live on the same square color
+    pub fn is_insufficient_material(&self)->bool{
+        let mut knights = 0;
+        let mut bishops = Vec::new();
+        for (sq,piece) in self.pieces(){
+            match piece.kind{
+                PieceKind::King => {}
+                PieceKind::Knight => knights+=1,
+                PieceKind::Bishop => bishops.push(sq),
+                _ => return false,
+            }
+        }
+        if knights+bishops.len() <= 1{
+            return true
+        }
+        knights == 0 && one_square_color(&bishops)
+    }
+
+    //whether `color` could mate by any series of legal moves, for the side
+    //whose opponent ran out of time (FIDE 6.9). A lone knight, or bishops all
+    //on one square color, only mate a king boxed in by its own pieces, so then
+    //it comes down to what the other side has
+    pub fn has_mating_material(&self,color:Color)->bool{
+        let mut knights = 0;
+        let mut bishops = Vec::new();
+        for (sq,piece) in self.pieces().filter(|(_,piece)| piece.color == color){
+            match piece.kind{
+                PieceKind::King => {}
+                PieceKind::Knight => knights+=1,
+                PieceKind::Bishop => bishops.push(sq),
+                _ => return true,
+            }
+        }
+        let lone_knight = knights == 1 && bishops.is_empty();
+        let one_color_bishops = knights == 0 && !bishops.is_empty() && one_square_color(&bishops);
+        if !(lone_knight || one_color_bishops){
+            return knights+bishops.len() > 0
+        }
+        //a piece to box the king in with: anything against the knight, against
+        //the bishops one that stands on the squares they never reach
+        self.pieces().any(|(sq,piece)| piece.color != color && match piece.kind{
+            PieceKind::King => false,
+            PieceKind::Bishop => lone_knight || sq.is_light() != bishops[0].is_light(),
+            _ => true,
+        })
+    }
+
+    //the en passant square, but only if the side to move can actually take there
+    pub fn legal_en_passant(&self)->Option<Square>{
+        let ep = self.en_passant?;
+        let capturable = self.legal_moves()
+            .iter()
+            .any(|mv| mv.to == ep && self.piece_at(mv.from).map(|p| p.kind) == Some(PieceKind::Pawn));
+        if capturable {Some(ep)} else {None}
+    }
+
+    //same position for the repetition rules: placement, side to move,
+    //castling rights and en passant captures that can really be played
+    pub fn same_position(&self,other:&Position)->bool{
+        self.board == other.board
+            && self.side_to_move == other.side_to_move
+            && self.castling == other.castling
+            && self.legal_en_passant() == other.legal_en_passant()
+    }
+
+    pub fn is_en_passant(&self,mv:&Move)->bool{
+        match self.piece_at(mv.from){
+            Some(piece) => piece.kind == PieceKind::Pawn
+                && Some(mv.to) == self.en_passant
+                && mv.from.file() != mv.to.file()
+                && self.piece_at(mv.to).is_none(),
+            None => false,
+        }
+    }
+
+    pub fn is_castling(&self,mv:&Move)->bool{
+        match self.piece_at(mv.from){
+            Some(piece) => piece.kind == PieceKind::King
+                && (mv.from.file() as i8-mv.to.file() as i8).abs() == 2,
+            None => false,
+        }
+    }
+
+    pub fn is_capture(&self,mv:&Move)->bool{
+        self.captured_square(mv).is_some()
+    }
+
+    //where the piece taken by mv stands; differs from mv.to for en passant
+    pub fn captured_square(&self,mv:&Move)->Option<Square>{
+        if self.is_en_passant(mv){
+            return Some(Square::new(mv.to.file(),mv.from.rank()))
+        }
+        self.piece_at(mv.to).map(|_| mv.to)
+    }
+
+    //rook (from,to) that goes along with a castling move
+    pub fn castling_rook(&self,mv:&Move)->Option<(Square,Square)>{
+        if !self.is_castling(mv){
+            return None
+        }
+        let rank = mv.from.rank();
+        if mv.to.file() > mv.from.file(){
+            Some((Square::new(7,rank),Square::new(5,rank)))
+        }
+        else{
+            Some((Square::new(0,rank),Square::new(3,rank)))
+        }
+    }
+
+    //plays mv without checking it; use legal_moves/is_legal to validate first
+    pub fn play(&mut self,mv:&Move){
+        let piece = match self.piece_at(mv.from){
+            Some(piece) => piece,
+            None => return,
+        };
+        let captured = self.captured_square(mv);
+        if let Some(sq) = captured{
+            self.set_piece(sq,None);
+        }
+        if let Some((rook_from,rook_to)) = self.castling_rook(mv){
+            let rook = self.piece_at(rook_from);
+            self.set_piece(rook_from,None);
+            self.set_piece(rook_to,rook);
+        }
+        self.set_piece(mv.from,None);
+        let landed = match mv.promotion{
+            Some(kind) => Piece::new(kind,piece.color),
+            None => piece,
+        };
+        self.set_piece(mv.to,Some(landed));
+
+        let mut castling = self.castling;
+        castling.touch(mv.from);
+        castling.touch(mv.to);
+        self.set_castling(castling);
+
+        self.en_passant = None;
+        if piece.kind == PieceKind::Pawn && (mv.from.rank() as i8-mv.to.rank() as i8).abs() == 2{
+            self.en_passant = Some(Square::new(mv.from.file(),(mv.from.rank()+mv.to.rank())/2));
+        }
+
+        if piece.kind == PieceKind::Pawn || captured.is_some(){
+            self.halfmove_clock = 0;
+        }
+        else{
+            self.halfmove_clock+=1;
+        }
+        if self.side_to_move == Color::Black{
+            self.fullmove_number+=1;
+        }
+        self.set_side_to_move(self.side_to_move.opposite());
+    }
+
+    fn pawn_moves(&self,from:Square,moves:&mut Vec<Move>){
+        let us = self.side_to_move;
+        let (forward,start_rank,last_rank) = match us{
+            Color::White => (1,1,7),
+            Color::Black => (-1,6,0),
+        };
+        let push = |to:Square,moves:&mut Vec<Move>| {
+            if to.rank() == last_rank{
+                for kind in PieceKind::PROMOTIONS{
+                    moves.push(Move{from,to,promotion:Some(kind)});
+                }
+            }
+            else{
+                moves.push(Move::new(from,to));
+            }
+        };
+        //pawns never capture straight ahead
+        if let Some(one) = from.offset(0,forward){
+            if self.piece_at(one).is_none(){
+                push(one,moves);
+                if from.rank() == start_rank{
+                    if let Some(two) = one.offset(0,forward){
+                        if self.piece_at(two).is_none(){
+                            push(two,moves);
+                        }
+                    }
+                }
+            }
+        }
+        //diagonal captures only, including en passant
+        for side_step in [-1,1]{
+            if let Some(to) = from.offset(side_step,forward){
+                let enemy = match self.piece_at(to){
+                    Some(piece) => piece.color != us,
+                    None => Some(to) == self.en_passant,
+                };
+                if enemy{
+                    push(to,moves);
+                }
+            }
+        }
+    }
+
+    fn step_moves(&self,from:Square,steps:&[(i8,i8)],moves:&mut Vec<Move>){
+        for (f,r) in steps.iter(){
+            if let Some(to) = from.offset(*f,*r){
+                match self.piece_at(to){
+                    Some(piece) if piece.color == self.side_to_move => {}
+                    _ => moves.push(Move::new(from,to)),
+                }
+            }
+        }
+    }
+
+    //walks each direction until the edge of the board or a piece,
+    //the first enemy piece on the way is included as a capture
+    fn slide_moves(&self,from:Square,directions:&[(i8,i8)],moves:&mut Vec<Move>){
+        for (f,r) in directions.iter(){
+            let mut current = from.offset(*f,*r);
+            while let Some(to) = current{
+                match self.piece_at(to){
+                    None => moves.push(Move::new(from,to)),
+                    Some(piece) => {
+                        if piece.color != self.side_to_move{
+                            moves.push(Move::new(from,to));
+                        }
+                        break;
+                    }
+                }
+                current = to.offset(*f,*r);
+            }
+        }
+    }
+
+    //castling: rights still there, empty squares in between,
+    //and the king may not be in, pass through or land in check
+    fn castling_moves(&self,from:Square,moves:&mut Vec<Move>){
+        let us = self.side_to_move;
+        let rank = match us{
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        if from != Square::new(4,rank) || self.is_attacked(from,us.opposite()){
+            return
+        }
+        let rook_home = |file:u8| self.piece_at(Square::new(file,rank)) == Some(Piece::new(PieceKind::Rook,us));
+        let empty = |files:&[u8]| files.iter().all(|file| self.piece_at(Square::new(*file,rank)).is_none());
+        let safe = |files:&[u8]| files.iter().all(|file| !self.is_attacked(Square::new(*file,rank),us.opposite()));
+        if self.castling.king_side(us) && rook_home(7) && empty(&[5,6]) && safe(&[5,6]){
+            moves.push(Move::new(from,Square::new(6,rank)));
+        }
+        if self.castling.queen_side(us) && rook_home(0) && empty(&[1,2,3]) && safe(&[3,2]){
+            moves.push(Move::new(from,Square::new(2,rank)));
+        }
+    }
+}
+
+impl Default for Position{
+    fn default()->Position{
+        Position::startpos()
+    }
+}
+
+//bishops on these squares could never meet
+fn one_square_color(squares:&[Square])->bool{
+    squares.iter().all(|sq| sq.is_light() == squares[0].is_light())
+}
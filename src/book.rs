@@ -0,0 +1,140 @@
+use std::cmp::Reverse;
+use crate::board::{Move, PieceKind, Position, Square};
+
+//Polyglot opening books (.bin): 16 byte big-endian entries sorted by
+//position key, see Position::zobrist
+//
+//  key     u64   Zobrist key of the position
+//  move    u16   to file (bits 0-2), to rank (3-5), from file (6-8),
+//                from rank (9-11), promotion (12-14: 0 none, 1 knight ... 4 queen)
+//  weight  u16   how good or how often played, relative to the other entries
+//  learn   u32   left alone
+
+const ENTRY_SIZE:usize = 16;
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct BookEntry{
+    pub mv:Move,
+    pub weight:u16,
+}
+
+//which of the book moves to play
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum BookChoice{
+    //the highest weight
+    Best,
+    //at random in proportion to the weights
+    Weighted,
+}
+
+pub struct Book{
+    //(key, move, weight) in file order
+    entries:Vec<(u64,u16,u16)>,
+}
+
+impl Book{
+    pub fn open(path:&str)->Result<Book,String>{
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}",path,e))?;
+        Book::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes:&[u8])->Result<Book,String>{
+        if !bytes.len().is_multiple_of(ENTRY_SIZE){
+            return Err(format!("{} bytes is not a whole number of book entries",bytes.len()))
+        }
+        let entries:Vec<(u64,u16,u16)> = bytes.chunks(ENTRY_SIZE)
+            .map(|entry| (
+                u64::from_be_bytes(entry[0..8].try_into().unwrap()),
+                u16::from_be_bytes(entry[8..10].try_into().unwrap()),
+                u16::from_be_bytes(entry[10..12].try_into().unwrap()),
+            ))
+            .collect();
+        if entries.windows(2).any(|pair| pair[0].0 > pair[1].0){
+            return Err("book entries are not sorted by key".to_string())
+        }
+        Ok(Book{entries})
+    }
+
+    pub fn len(&self)->usize{
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self)->bool{
+        self.entries.is_empty()
+    }
+
+    //the book's moves for this position, highest weight first; entries that
+    //are not legal here (key collisions, broken books) are left out
+    pub fn moves(&self,position:&Position)->Vec<BookEntry>{
+        let key = position.zobrist();
+        let first = self.entries.partition_point(|(entry_key,_,_)| *entry_key < key);
+        let mut moves:Vec<BookEntry> = self.entries[first..].iter()
+            .take_while(|(entry_key,_,_)| *entry_key == key)
+            .filter_map(|(_,mv,weight)| {
+                let mv = decode_move(position,*mv)?;
+                Some(BookEntry{mv,weight:*weight})
+            })
+            .filter(|entry| position.is_legal(&entry.mv))
+            .collect();
+        moves.sort_by_key(|entry| Reverse(entry.weight));
+        moves
+    }
+
+    //None once the game has left the book; `random` is only used by
+    //BookChoice::Weighted and can come from any source
+    pub fn pick(&self,position:&Position,choice:BookChoice,random:u64)->Option<Move>{
+        let moves = self.moves(position);
+        match choice{
+            BookChoice::Best => moves.first().map(|entry| entry.mv),
+            BookChoice::Weighted => {
+                let total:u64 = moves.iter().map(|entry| entry.weight as u64).sum();
+                if total == 0{
+                    return moves.first().map(|entry| entry.mv)
+                }
+                let mut target = xorshift(random) % total;
+                for entry in moves.iter(){
+                    if target < entry.weight as u64{
+                        return Some(entry.mv)
+                    }
+                    target-=entry.weight as u64;
+                }
+                None
+            }
+        }
+    }
+}
+
+//spreads a weak seed (a clock reading, a counter) over all 64 bits
+fn xorshift(seed:u64)->u64{
+    let mut x = seed | 1;
+    for _ in 0..4{
+        x^=x << 13;
+        x^=x >> 7;
+        x^=x << 17;
+    }
+    x
+}
+
+//Polyglot writes castling as the king taking its own rook, e1h1 for e1g1
+fn decode_move(position:&Position,raw:u16)->Option<Move>{
+    let square = |bits:u16| Square::new((bits & 7) as u8,((bits >> 3) & 7) as u8);
+    let from = square(raw >> 6);
+    let mut to = square(raw);
+    let promotion = match (raw >> 12) & 7{
+        0 => None,
+        1 => Some(PieceKind::Knight),
+        2 => Some(PieceKind::Bishop),
+        3 => Some(PieceKind::Rook),
+        4 => Some(PieceKind::Queen),
+        _ => return None,
+    };
+    let king = position.piece_at(from).map(|piece| piece.kind) == Some(PieceKind::King);
+    if king && from.file() == 4 && from.rank() == to.rank(){
+        match to.file(){
+            7 => to = Square::new(6,to.rank()),
+            0 => to = Square::new(2,to.rank()),
+            _ => {}
+        }
+    }
+    Some(Move{from,to,promotion})
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+use std::time::Duration;
+use crate::board::Color;
+
+//a chess clock: each side's time runs down while it is on move, the time
+//control adds something back for every move; the caller feeds it the time
+//that passed (tick) and presses it after every move (press)
+
+//what a move earns on top of the base time
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Bonus{
+    //the bonus is added after every move
+    Fischer,
+    //the time used on a move is given back, up to the bonus
+    Bronstein,
+    //the clock only starts running once the bonus has passed (simple or US delay)
+    Delay,
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct TimeControl{
+    pub base:Duration,
+    pub bonus:Duration,
+    pub kind:Bonus,
+}
+
+impl TimeControl{
+    pub fn new(base:Duration,bonus:Duration,kind:Bonus)->TimeControl{
+        TimeControl{base,bonus,kind}
+    }
+
+    //"300+3" for the PGN TimeControl tag; delays have no notation there
+    pub fn pgn_tag(&self)->Option<String>{
+        if self.bonus.is_zero(){
+            return Some(self.base.as_secs().to_string())
+        }
+        match self.kind{
+            Bonus::Fischer => Some(format!("{}+{}",self.base.as_secs(),self.bonus.as_secs())),
+            _ => None,
+        }
+    }
+}
+
+//minutes and seconds per move: "5+3", "15+10 delay"
+impl fmt::Display for TimeControl{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        let seconds = self.base.as_secs();
+        if seconds.is_multiple_of(60){
+            write!(f,"{}",seconds/60)?;
+        }
+        else{
+            write!(f,"{}:{:02}",seconds/60,seconds%60)?;
+        }
+        write!(f,"+{}",self.bonus.as_secs())?;
+        match self.kind{
+            Bonus::Fischer => Ok(()),
+            Bonus::Bronstein => write!(f," Bronstein"),
+            Bonus::Delay => write!(f," delay"),
+        }
+    }
+}
+
+#[derive(Clone,Debug)]
+pub struct Clock{
+    control:TimeControl,
+    //white's and black's, see Color::index
+    remaining:[Duration;2],
+    //whose time is running; None before the first move and once stopped
+    running:Option<Color>,
+    //how long the side on move has been thinking
+    used:Duration,
+}
+
+impl Clock{
+    //both sides on the base time, nobody's clock running yet
+    pub fn new(control:TimeControl)->Clock{
+        Clock{control,remaining:[control.base;2],running:None,used:Duration::ZERO}
+    }
+
+    pub fn control(&self)->TimeControl{
+        self.control
+    }
+
+    pub fn remaining(&self,color:Color)->Duration{
+        self.remaining[color.index()]
+    }
+
+    //puts a reading back, e.g. from the [%clk] of a recorded move
+    pub fn set_remaining(&mut self,color:Color,remaining:Duration){
+        self.remaining[color.index()] = remaining;
+    }
+
+    pub fn running(&self)->Option<Color>{
+        self.running
+    }
+
+    //starts `color`'s turn
+    pub fn start(&mut self,color:Color){
+        self.running = Some(color);
+        self.used = Duration::ZERO;
+    }
+
+    pub fn stop(&mut self){
+        self.running = None;
+    }
+
+    //what is left of the delay before the running clock counts down
+    pub fn delay_left(&self)->Duration{
+        match (self.running,self.control.kind){
+            (Some(_),Bonus::Delay) => self.control.bonus.saturating_sub(self.used),
+            _ => Duration::ZERO,
+        }
+    }
+
+    pub fn tick(&mut self,elapsed:Duration){
+        let color = match self.running{
+            Some(color) => color,
+            None => return,
+        };
+        let charged = match self.control.kind{
+            Bonus::Delay => elapsed.saturating_sub(self.delay_left()),
+            _ => elapsed,
+        };
+        self.used+=elapsed;
+        let remaining = &mut self.remaining[color.index()];
+        *remaining = remaining.saturating_sub(charged);
+    }
+
+    //ends `mover`'s turn: adds the bonus and starts the other side's clock;
+    //returns the time `mover` has left. A side that runs out stays out, see flagged
+    pub fn press(&mut self,mover:Color)->Duration{
+        let used = if self.running == Some(mover) {self.used} else {Duration::ZERO};
+        let remaining = &mut self.remaining[mover.index()];
+        if !remaining.is_zero(){
+            match self.control.kind{
+                Bonus::Fischer => *remaining+=self.control.bonus,
+                Bonus::Bronstein => *remaining+=used.min(self.control.bonus),
+                Bonus::Delay => {}
+            }
+        }
+        let left = *remaining;
+        self.start(mover.opposite());
+        left
+    }
+
+    //the side whose time ran out, if any
+    pub fn flagged(&self)->Option<Color>{
+        [Color::White,Color::Black].into_iter().find(|color| self.remaining(*color).is_zero())
+    }
+}
@@ -0,0 +1,287 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::board::{Color, Move, Position};
+
+//client side of UCI: runs an engine executable and talks to it over its
+//stdin/stdout; a reader thread turns its output into lines on a channel, so
+//a hung or crashed engine shows up as a timeout or a closed channel
+
+//how long an engine gets to answer "uci" and "isready"
+const HANDSHAKE_TIMEOUT:Duration = Duration::from_secs(5);
+//extra time on top of the search limit before the engine counts as hung
+const BESTMOVE_GRACE:Duration = Duration::from_secs(5);
+
+//what to run, read from a small config file:
+//
+//  path = /usr/local/bin/stockfish
+//  args = --some-flag
+//  option Threads = 2
+//  option Skill Level = 10
+#[derive(Clone,Debug,PartialEq)]
+pub struct EngineConfig{
+    pub path:String,
+    pub args:Vec<String>,
+    //sent as "setoption name <name> value <value>" after the handshake
+    pub options:Vec<(String,String)>,
+}
+
+impl EngineConfig{
+    pub fn load(path:&str)->Result<EngineConfig,String>{
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}",path,e))?;
+        EngineConfig::parse(&text)
+    }
+
+    //blank lines and lines starting with '#' are skipped
+    pub fn parse(text:&str)->Result<EngineConfig,String>{
+        let mut config = EngineConfig{path:String::new(),args:Vec::new(),options:Vec::new()};
+        for (i,line) in text.lines().enumerate(){
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#'){
+                continue
+            }
+            let (key,value) = line.split_once('=').ok_or(format!("line {}: expected key = value",i+1))?;
+            let (key,value) = (key.trim(),value.trim());
+            match key{
+                "path" => config.path = value.to_string(),
+                "args" => config.args = value.split_whitespace().map(|arg| arg.to_string()).collect(),
+                _ => match key.strip_prefix("option "){
+                    Some(name) => config.options.push((name.trim().to_string(),value.to_string())),
+                    None => return Err(format!("line {}: unknown key '{}'",i+1,key)),
+                },
+            }
+        }
+        if config.path.is_empty(){
+            return Err("no engine path given".to_string())
+        }
+        Ok(config)
+    }
+}
+
+//an option as the engine announces it: "option name Hash type spin default 16 min 1 max 33554432"
+#[derive(Clone,Debug,PartialEq)]
+pub struct EngineOption{
+    pub name:String,
+    pub kind:String,
+    pub default:Option<String>,
+}
+
+impl EngineOption{
+    fn parse(line:&str)->Option<EngineOption>{
+        let rest = line.strip_prefix("option name ")?;
+        let (name,rest) = rest.split_once(" type ")?;
+        let mut words = rest.split_whitespace();
+        let kind = words.next()?.to_string();
+        let default = rest.split_once(" default ").map(|(_,default)| {
+            //the default runs up to the next keyword
+            default.split(" min ").next().unwrap_or(default)
+                .split(" var ").next().unwrap_or(default)
+                .trim().to_string()
+        });
+        Some(EngineOption{name:name.trim().to_string(),kind,default})
+    }
+}
+
+//how long the engine may think, in UCI "go" terms
+#[derive(Clone,Copy,Debug)]
+pub enum EngineLimits{
+    Depth(u32),
+    MoveTime(Duration),
+    //time left on both clocks with their increments
+    Clock{white:Duration,black:Duration,white_increment:Duration,black_increment:Duration},
+}
+
+impl EngineLimits{
+    fn go_command(&self)->String{
+        match self{
+            EngineLimits::Depth(depth) => format!("go depth {}",depth),
+            EngineLimits::MoveTime(time) => format!("go movetime {}",time.as_millis()),
+            EngineLimits::Clock{white,black,white_increment,black_increment} => format!(
+                "go wtime {} btime {} winc {} binc {}",
+                white.as_millis(),black.as_millis(),white_increment.as_millis(),black_increment.as_millis()),
+        }
+    }
+
+    //the longest a well behaved engine can take before sending bestmove
+    fn deadline(&self,side_time:Option<Duration>)->Option<Duration>{
+        match self{
+            EngineLimits::Depth(_) => None,
+            EngineLimits::MoveTime(time) => Some(*time+BESTMOVE_GRACE),
+            EngineLimits::Clock{..} => side_time.map(|time| time+BESTMOVE_GRACE),
+        }
+    }
+}
+
+pub struct Engine{
+    child:Child,
+    stdin:ChildStdin,
+    lines:Receiver<String>,
+    //"id name" from the handshake
+    pub name:String,
+    pub options:Vec<EngineOption>,
+}
+
+impl Engine{
+    //launches the engine and goes through the uci/isready handshake,
+    //setting the configured options in between
+    pub fn start(config:&EngineConfig)->Result<Engine,String>{
+        let mut child = Command::new(&config.path)
+            .args(&config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("could not start {}: {}",config.path,e))?;
+        let stdin = child.stdin.take().ok_or("no stdin for the engine")?;
+        let stdout = child.stdout.take().ok_or("no stdout for the engine")?;
+
+        let (sender,lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines(){
+                match line{
+                    Ok(line) => if sender.send(line).is_err() {break},
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let mut engine = Engine{child,stdin,lines,name:config.path.clone(),options:Vec::new()};
+        engine.send("uci")?;
+        loop{
+            let line = engine.receive(HANDSHAKE_TIMEOUT)?;
+            if let Some(name) = line.strip_prefix("id name "){
+                engine.name = name.trim().to_string();
+            }
+            else if let Some(option) = EngineOption::parse(&line){
+                engine.options.push(option);
+            }
+            else if line.trim() == "uciok"{
+                break
+            }
+        }
+        for (name,value) in config.options.iter(){
+            if !engine.options.iter().any(|option| option.name.eq_ignore_ascii_case(name)){
+                return Err(format!("{} has no option '{}'",engine.name,name))
+            }
+            engine.send(&format!("setoption name {} value {}",name,value))?;
+        }
+        engine.ready()?;
+        Ok(engine)
+    }
+
+    pub fn new_game(&mut self)->Result<(),String>{
+        self.send("ucinewgame")?;
+        self.ready()
+    }
+
+    //searches the position reached from `start` by `moves` and returns the
+    //engine's bestmove; setting `stop` makes the engine answer right away
+    pub fn best_move(&mut self,start:&Position,moves:&[Move],limits:EngineLimits,stop:&AtomicBool)->Result<Move,String>{
+        let mut position = *start;
+        for mv in moves{
+            position.play(mv);
+        }
+        let mut command = if *start == Position::startpos(){
+            "position startpos".to_string()
+        }
+        else{
+            format!("position fen {}",start.to_fen())
+        };
+        if !moves.is_empty(){
+            command.push_str(" moves");
+            for mv in moves{
+                command.push_str(&format!(" {}",mv));
+            }
+        }
+        self.send(&command)?;
+        self.send(&limits.go_command())?;
+
+        let side_time = match limits{
+            EngineLimits::Clock{white,black,..} => Some(match position.side_to_move(){
+                Color::White => white,
+                Color::Black => black,
+            }),
+            _ => None,
+        };
+        let deadline = limits.deadline(side_time).map(|limit| Instant::now()+limit);
+        let mut stopped = false;
+        loop{
+            if !stopped && stop.load(Ordering::Relaxed){
+                self.send("stop")?;
+                stopped = true;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() > deadline){
+                return Err(format!("{} did not answer in time",self.name))
+            }
+            let line = match self.lines.recv_timeout(Duration::from_millis(10)){
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return Err(self.exit_reason()),
+            };
+            let mut words = line.split_whitespace();
+            if words.next() == Some("bestmove"){
+                let text = words.next().unwrap_or("");
+                return Move::from_uci(text)
+                    .filter(|mv| position.is_legal(mv))
+                    .ok_or(format!("{} played an illegal move '{}'",self.name,text))
+            }
+        }
+    }
+
+    //true while the process is running
+    pub fn is_alive(&mut self)->bool{
+        matches!(self.child.try_wait(),Ok(None))
+    }
+
+    fn ready(&mut self)->Result<(),String>{
+        self.send("isready")?;
+        loop{
+            if self.receive(HANDSHAKE_TIMEOUT)?.trim() == "readyok"{
+                return Ok(())
+            }
+        }
+    }
+
+    fn send(&mut self,command:&str)->Result<(),String>{
+        writeln!(self.stdin,"{}",command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|_| self.exit_reason())
+    }
+
+    fn receive(&mut self,timeout:Duration)->Result<String,String>{
+        match self.lines.recv_timeout(timeout){
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err(format!("{} did not answer in time",self.name)),
+            Err(RecvTimeoutError::Disconnected) => Err(self.exit_reason()),
+        }
+    }
+
+    fn exit_reason(&mut self)->String{
+        //the output closes a moment before the process is gone
+        thread::sleep(Duration::from_millis(50));
+        match self.child.try_wait(){
+            Ok(Some(status)) => format!("{} exited ({})",self.name,status),
+            _ => format!("{} stopped talking",self.name),
+        }
+    }
+}
+
+//asks the engine to quit and makes sure it does
+impl Drop for Engine{
+    fn drop(&mut self){
+        let _ = writeln!(self.stdin,"quit");
+        let _ = self.stdin.flush();
+        let deadline = Instant::now()+Duration::from_millis(500);
+        while Instant::now() < deadline{
+            if !matches!(self.child.try_wait(),Ok(None)){
+                return
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,174 @@
+use crate::board::{CastlingRights, Color, Piece, PieceKind, Position, Square};
+
+//Forsyth-Edwards Notation: placement, side to move, castling rights,
+//en passant square, halfmove clock and fullmove number
+
+pub const STARTPOS:&str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+impl Position{
+    //the clocks may be left out, they default to "0 1"
+    pub fn from_fen(fen:&str)->Result<Position,String>{
+        let fields:Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 || fields.len() > 6{
+            return Err(format!("expected 4 to 6 fields, got {}",fields.len()))
+        }
+        let mut position = Position::empty();
+
+        let ranks:Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8{
+            return Err(format!("expected 8 ranks, got {}",ranks.len()))
+        }
+        //FEN starts with the 8th rank
+        for (i,row) in ranks.iter().enumerate(){
+            let rank = 7-i as u8;
+            let mut file = 0u8;
+            for c in row.chars(){
+                if let Some(empty) = c.to_digit(10){
+                    file+=empty as u8;
+                }
+                else{
+                    let kind = PieceKind::from_letter(c).ok_or(format!("unknown piece '{}'",c))?;
+                    let color = if c.is_ascii_uppercase() {Color::White} else {Color::Black};
+                    if file >= 8{
+                        return Err(format!("rank {} is too long",rank+1))
+                    }
+                    if kind == PieceKind::Pawn && (rank == 0 || rank == 7){
+                        return Err(format!("pawn on rank {}",rank+1))
+                    }
+                    position.set_piece(Square::new(file,rank),Some(Piece::new(kind,color)));
+                    file+=1;
+                }
+                if file > 8{
+                    return Err(format!("rank {} is too long",rank+1))
+                }
+            }
+            if file != 8{
+                return Err(format!("rank {} is too short",rank+1))
+            }
+        }
+        for color in [Color::White,Color::Black]{
+            let kings = position.pieces()
+                .filter(|(_,piece)| *piece == Piece::new(PieceKind::King,color))
+                .count();
+            if kings != 1{
+                return Err(format!("{:?} needs exactly one king",color))
+            }
+        }
+
+        match fields[1]{
+            "w" => position.set_side_to_move(Color::White),
+            "b" => position.set_side_to_move(Color::Black),
+            other => return Err(format!("unknown side to move '{}'",other)),
+        }
+
+        let mut castling = CastlingRights::none();
+        if fields[2] != "-"{
+            for c in fields[2].chars(){
+                match c{
+                    'K' => castling.white_king_side = true,
+                    'Q' => castling.white_queen_side = true,
+                    'k' => castling.black_king_side = true,
+                    'q' => castling.black_queen_side = true,
+                    other => return Err(format!("unknown castling right '{}'",other)),
+                }
+            }
+        }
+        position.set_castling(castling);
+
+        if fields[3] != "-"{
+            let sq = Square::from_name(fields[3]).ok_or(format!("bad en passant square '{}'",fields[3]))?;
+            //behind a pawn of the side not to move that has just gone two squares,
+            //with nothing on the square it passed or the one it came from
+            let (mover,rank,step) = match position.side_to_move(){
+                Color::White => (Color::Black,5,-1),
+                Color::Black => (Color::White,2,1),
+            };
+            let pushed = sq.rank() == rank
+                && sq.offset(0,step).and_then(|pawn| position.piece_at(pawn)) == Some(Piece::new(PieceKind::Pawn,mover))
+                && position.piece_at(sq).is_none()
+                && sq.offset(0,-step).and_then(|origin| position.piece_at(origin)).is_none();
+            if !pushed{
+                return Err(format!("bad en passant square '{}'",fields[3]))
+            }
+            position.set_en_passant(Some(sq));
+        }
+
+        //the side to move could take the king
+        if position.is_in_check(position.side_to_move().opposite()){
+            return Err(format!("{:?} is in check but not to move",position.side_to_move().opposite()))
+        }
+
+        let halfmove_clock = match fields.get(4){
+            Some(field) => field.parse().map_err(|_| format!("bad halfmove clock '{}'",field))?,
+            None => 0,
+        };
+        let fullmove_number = match fields.get(5){
+            Some(field) => field.parse().map_err(|_| format!("bad fullmove number '{}'",field))?,
+            None => 1,
+        };
+        position.set_clocks(halfmove_clock,fullmove_number);
+        Ok(position)
+    }
+
+    pub fn to_fen(&self)->String{
+        let mut placement = String::new();
+        for rank in (0..8).rev(){
+            let mut empty = 0;
+            for file in 0..8{
+                match self.piece_at(Square::new(file,rank)){
+                    Some(piece) => {
+                        if empty > 0{
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(piece_letter(&piece));
+                    }
+                    None => empty+=1,
+                }
+            }
+            if empty > 0{
+                placement.push_str(&empty.to_string());
+            }
+            if rank > 0{
+                placement.push('/');
+            }
+        }
+
+        let side = match self.side_to_move(){
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let rights = self.castling();
+        let mut castling = String::new();
+        for (right,c) in [
+            (rights.white_king_side,'K'),
+            (rights.white_queen_side,'Q'),
+            (rights.black_king_side,'k'),
+            (rights.black_queen_side,'q'),
+        ]{
+            if right{
+                castling.push(c);
+            }
+        }
+        if castling.is_empty(){
+            castling.push('-');
+        }
+
+        let en_passant = match self.en_passant(){
+            Some(sq) => sq.name(),
+            None => "-".to_string(),
+        };
+
+        format!("{} {} {} {} {} {}",
+            placement,side,castling,en_passant,self.halfmove_clock(),self.fullmove_number())
+    }
+}
+
+//uppercase for white, lowercase for black
+pub fn piece_letter(piece:&Piece)->char{
+    match piece.color{
+        Color::White => piece.kind.letter().to_ascii_uppercase(),
+        Color::Black => piece.kind.letter(),
+    }
+}
@@ -0,0 +1,79 @@
+use crate::board::{Move, Position, Square};
+
+//the moves played on a board, to take them back and play them again; it only
+//knows squares, whatever shows the pieces finds them there when it takes a
+//move back, so a captured piece that came back as a new object is no different
+
+//what taking a move back does to the board; the captured piece is whatever
+//the caller keeps about it (T), the history only hands it back
+#[derive(Clone,Debug,PartialEq)]
+pub struct TakeBack<T>{
+    pub mv:Move,
+    //the position to go back to
+    pub before:Position,
+    //the piece standing on mv.to goes back to mv.from, a promoted one as a pawn
+    //castling: the rook goes from the first square back to the second
+    pub rook:Option<(Square,Square)>,
+    //the captured piece and where it stood (next to mv.to for en passant)
+    pub captured:Option<(T,Square)>,
+}
+
+#[derive(Clone,Debug)]
+struct Played<T>{
+    mv:Move,
+    before:Position,
+    captured:Option<T>,
+}
+
+#[derive(Clone,Debug)]
+pub struct History<T>{
+    //oldest first
+    played:Vec<Played<T>>,
+    //moves taken back, the next one to play again last
+    taken_back:Vec<Move>,
+}
+
+impl<T> Default for History<T>{
+    fn default()->Self{
+        History{played:Vec::new(),taken_back:Vec::new()}
+    }
+}
+
+impl<T> History<T>{
+    pub fn new()->History<T>{
+        History::default()
+    }
+
+    //a move played on before, with the piece it took;
+    //playing the move taken back last keeps the rest of the redo line
+    pub fn push(&mut self,before:&Position,mv:Move,captured:Option<T>){
+        if self.taken_back.last() == Some(&mv){
+            self.taken_back.pop();
+        }
+        else{
+            self.taken_back.clear();
+        }
+        self.played.push(Played{mv,before:*before,captured});
+    }
+
+    //mv, the last move on the board, is taken back and can be played again;
+    //None for moves from before the history started (a loaded game),
+    //the caller has to set those positions up itself
+    pub fn take_back(&mut self,mv:Move)->Option<TakeBack<T>>{
+        self.taken_back.push(mv);
+        let played = self.played.pop()?;
+        let rook = played.before.castling_rook(&played.mv).map(|(from,to)| (to,from));
+        let captured = played.before.captured_square(&played.mv);
+        Some(TakeBack{
+            mv:played.mv,
+            before:played.before,
+            rook,
+            captured:played.captured.zip(captured),
+        })
+    }
+
+    //the move to play again, if any
+    pub fn redo(&self)->Option<Move>{
+        self.taken_back.last().copied()
+    }
+}
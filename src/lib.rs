@@ -1 +1,12 @@
-pub mod dbmu;
+pub mod board;
+pub mod fen;
+pub mod san;
+pub mod pgn;
+pub mod search;
+pub mod engine;
+pub mod perft;
+pub mod zobrist;
+pub mod book;
+pub mod syzygy;
+pub mod clock;
+pub mod history;
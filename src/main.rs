@@ -3,14 +3,28 @@ use std::f32::consts::PI;
 use std::collections::HashMap;
 use bevy::{prelude::*, transform};
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContext};
 use bevy_mod_picking::*;
-use chess::dbmu::Database;
-use std::time::Duration;
+use chess::board::{self, Position, Move, PieceKind};
+use chess::board::Color as PieceColor;
+use chess::pgn::{self, PgnGame};
+use chess::search::{self, Limits};
+use chess::engine::{Engine, EngineConfig, EngineLimits};
+use chess::book::{Book, BookChoice};
+use chess::syzygy::{MoveProbe, Tablebase, Wdl};
+use chess::clock::{Bonus, Clock, TimeControl};
+use chess::history::History;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::thread::sleep;
 use std::thread;
 
 pub const HEIGHT: f32 = 720.0;
 pub const WIDTH: f32 = 1280.0;
+//board geometry, see square_translation
+pub const SQUARE_SIZE: f32 = 6.0;
+pub const BOARD_EDGE: f32 = 21.0;
 
 fn main(){
     App::new()
@@ -30,17 +44,41 @@ fn main(){
         // Inspector Setup
         .add_plugin(WorldInspectorPlugin)
         .register_type::<ChessBoard>()
+        .add_event::<LoadGame>()
+        .add_event::<PlayMove>()
+        .init_resource::<FenPanel>()
+        .init_resource::<PgnPanel>()
+        .init_resource::<AiPlayer>()
+        .init_resource::<OpeningBook>()
+        .init_resource::<Tablebases>()
+        .init_resource::<GameClock>()
         // Our Systems
         .add_startup_system_to_stage(StartupStage::PreStartup, asset_loading)
         .add_startup_system(spawn_light)
         .add_startup_system(spawn_basic_chess_board)
         .add_startup_system(spawn_camera)
+        .add_startup_system(load_opening_book)
+        .add_startup_system(load_tablebases)
         .add_system(camera_controls)
         .add_system(chess_movement_script)
         .add_system(chess_data_piece)
         .add_system(chess_data_square)
         .add_system(purge_square_script)
-        .add_system(update_board_state)
+        .add_system(game_status_script)
+        .add_system(promotion_picker)
+        .add_system(game_over_overlay)
+        .add_system(fen_panel)
+        .add_system(pgn_panel)
+        .add_system(undo_redo_script)
+        .add_system(ai_script)
+        .add_system(ai_panel)
+        .add_system(book_panel)
+        .add_system(tablebase_panel)
+        .add_system(play_move_script.after(chess_movement_script).after(promotion_picker).after(pgn_panel).after(undo_redo_script).after(ai_script))
+        .add_system(load_game_script)
+        .add_system(draw_claim_panel)
+        .add_system(clock_script)
+        .add_system(clock_panel)
         // .add_system(test_selection)
         .run();
 }
@@ -64,327 +102,333 @@ pub struct ChessBoard{
     bpawn: Handle<Scene>,
 }
 
+impl ChessBoard{
+    fn scene(&self,tag:&str,color:&PieceColor)->Handle<Scene>{
+        let handle = match (color,tag){
+            (PieceColor::White,"king") => &self.wking,
+            (PieceColor::White,"queen") => &self.wqueen,
+            (PieceColor::White,"rook") => &self.wrook,
+            (PieceColor::White,"bishop") => &self.wbishop,
+            (PieceColor::White,"knight") => &self.wknight,
+            (PieceColor::White,_) => &self.wpawn,
+            (PieceColor::Black,"king") => &self.bking,
+            (PieceColor::Black,"queen") => &self.bqueen,
+            (PieceColor::Black,"rook") => &self.brook,
+            (PieceColor::Black,"bishop") => &self.bbishop,
+            (PieceColor::Black,"knight") => &self.bknight,
+            (PieceColor::Black,_) => &self.bpawn,
+        };
+        handle.clone()
+    }
+}
+
 #[derive(Component)]
+#[derive(Clone,Debug)]
 struct Piece{
     tag:String,
+    color:PieceColor,
     move_count:i32,
 }
 
 #[derive(Component)]
 struct Square;
 
-impl Piece{
-    fn new(tag:String)->Piece{
-        Piece { 
-            tag,
-            move_count:0
-                
-        }
+//where the PGN window saves and opens games, and how that went
+#[derive(Resource)]
+struct PgnPanel{
+    path:String,
+    status:Option<String>,
+    //games of the last opened file
+    games:Vec<PgnGame>,
+    replay:Option<Replay>,
+}
+
+//a game from a PGN file being stepped through, `ply` moves of it are on the board
+struct Replay{
+    game:PgnGame,
+    ply:usize,
+}
+
+impl Default for PgnPanel{
+    fn default()->PgnPanel{
+        PgnPanel{path:"game.pgn".to_string(),status:None,games:Vec::new(),replay:None}
     }
-    //offset represents distance to move forward (def:6) -6 in some cases
-    fn possible_moves(
-        &self,
-        x:&f32,
-        z:&f32,
-        offset:f32,
-        db:&ResMut<BoardDataBase>
-        )->Vec<(f32,f32)>{
-        let mut returns = Vec::new();
-        if self.tag =="king".to_string(){
-            returns.push((x+offset,*z));
-            returns.push((x-offset,*z));
-            returns.push((x+offset,z+offset));
-            returns.push((x-offset,z+offset));
-            returns.push((x+offset,z-offset));
-            returns.push((x-offset,z-offset));
-            returns.push((*x,z+offset));
-            returns.push((*x,z-offset));
-            //TODO:castling 
+}
+
+//the side the computer plays, if any, and how well
+#[derive(Resource)]
+struct AiPlayer{
+    color:Option<PieceColor>,
+    //index into search::LEVELS
+    level:usize,
+    thinking:Option<Thinking>,
+    //external UCI engine playing instead of the built-in search, started
+    //from the config file at `config_path` (see chess::engine::EngineConfig)
+    engine:Option<Arc<Mutex<Engine>>>,
+    config_path:String,
+    //an engine being started on its own thread, the handshake can take seconds
+    starting:Option<Background<Result<Engine,String>>>,
+    //a game was set up since the engine last searched, it gets a ucinewgame first
+    new_game:bool,
+    //what went wrong with the engine, or which one is running
+    status:Option<String>,
+    //how to play from the opening book while the game is in it, None to always search
+    book:Option<BookChoice>,
+}
+
+impl Default for AiPlayer{
+    fn default()->AiPlayer{
+        AiPlayer{
+            color:None,
+            level:1,
+            thinking:None,
+            engine:None,
+            config_path:"engine.cfg".to_string(),
+            starting:None,
+            new_game:false,
+            status:None,
+            book:Some(BookChoice::Weighted),
         }
+    }
+}
 
-        if self.tag == "queen".to_string(){
-            let mut flag_left = true;
-            let mut flag_right = true;
-            let mut flag_front = true;
-            let mut flag_back = true;
-
-            //front
-            let mut count = 1.;
-            while flag_front{
-                let pos_x = *x;
-                let pos_z = z+count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_front = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while front
-
-            count =1.;
-            while flag_back{
-                let pos_x = *x;
-                let pos_z = z-count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_back = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while back
-
-            count = 1.;
-            while flag_right{
-                let pos_x = x+count*offset;
-                let pos_z = *z;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_x.abs() > 24. {
-                    flag_right = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while right
-
-            count = 1.;
-            while flag_left{
-                let pos_x = x-count*offset;
-                let pos_z = *z;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_x.abs() > 24. {
-                    flag_left = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while left
-
-            let mut flag_1 = true;
-            let mut flag_2 = true;
-            let mut flag_3 = true;
-            let mut flag_4 = true;
-            let mut count = 1.;
-            while flag_1{
-                let pos_x = x+count*offset;
-                let pos_z = z+count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_1 = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while 1
-            count = 1.;
-            while flag_2{
-                let pos_x = x-count*offset;
-                let pos_z = z+count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_2 = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while 2
-
-            count = 1.;
-            while flag_3{
-                let pos_x = x+count*offset;
-                let pos_z = z-count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_3 = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while 3
-
-            count = 1.;
-            while flag_4{
-                let pos_x = x-count*offset;
-                let pos_z = z-count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_4 = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while 4
+//time controls offered in the clock window: minutes and seconds of bonus
+const TIME_CONTROLS:[(u64,u64);6] = [(1,0),(3,2),(5,0),(10,5),(15,10),(90,30)];
+
+//the clocks of the game on the board and the time control for new games,
+//as set in the clock window; like most servers, the clocks start with the first move
+#[derive(Resource)]
+struct GameClock{
+    timed:bool,
+    minutes:u64,
+    //seconds, used as `kind` says
+    bonus:u64,
+    kind:Bonus,
+    //None for untimed games
+    clock:Option<Clock>,
+}
+
+impl Default for GameClock{
+    fn default()->GameClock{
+        GameClock{timed:false,minutes:5,bonus:3,kind:Bonus::Fischer,clock:None}
+    }
+}
 
+impl GameClock{
+    fn control(&self)->Option<TimeControl>{
+        if !self.timed{
+            return None
         }
+        Some(TimeControl::new(Duration::from_secs(self.minutes*60),Duration::from_secs(self.bonus),self.kind))
+    }
 
-        if self.tag =="pawn".to_string(){
-            returns.push((*x,z+offset));
-            if self.move_count==0 && !db.data.contains_key(&chess_pos(*x,z+1.*offset)){
-                returns.push((*x,z+2.*offset));
-            }
+    //clocks for a game set up from `record`, under the time control chosen for new games
+    fn start_game(&mut self,record:&PgnGame){
+        self.clock = self.control().map(|control| restored_clock(control,record));
+    }
+
+    //puts the clocks back after moves were taken back
+    fn rewind(&mut self,record:&PgnGame){
+        if let Some(clock) = &mut self.clock{
+            *clock = restored_clock(clock.control(),record);
         }
+    }
+}
 
-        if self.tag == "rook".to_string(){
-            let mut flag_left = true;
-            let mut flag_right = true;
-            let mut flag_front = true;
-            let mut flag_back = true;
-
-            //front
-            let mut count = 1.;
-            while flag_front{
-                let pos_x = *x;
-                let pos_z = z+count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_front = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while front
-
-            count =1.;
-            while flag_back{
-                let pos_x = *x;
-                let pos_z = z-count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_back = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while back
-
-            count = 1.;
-            while flag_right{
-                let pos_x = x+count*offset;
-                let pos_z = *z;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_x.abs() > 24. {
-                    flag_right = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while right
-
-            count = 1.;
-            while flag_left{
-                let pos_x = x-count*offset;
-                let pos_z = *z;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_x.abs() > 24. {
-                    flag_left = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while left
+//the clocks where `record` left them: each side on its last [%clk] reading or
+//the base time, the side to move's clock running once a move has been played
+fn restored_clock(control:TimeControl,record:&PgnGame)->Clock{
+    let mut clock = Clock::new(control);
+    let mut mover = record.start.side_to_move();
+    for played in record.moves.iter(){
+        if let Some(reading) = played.clock{
+            clock.set_remaining(mover,reading);
         }
-        if self.tag == "bishop".to_string(){
-            let mut flag_1 = true;
-            let mut flag_2 = true;
-            let mut flag_3 = true;
-            let mut flag_4 = true;
-            let mut count = 1.;
-            while flag_1{
-                let pos_x = x+count*offset;
-                let pos_z = z+count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_1 = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while 1
-            count = 1.;
-            while flag_2{
-                let pos_x = x-count*offset;
-                let pos_z = z+count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_2 = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while 2
-
-            count = 1.;
-            while flag_3{
-                let pos_x = x+count*offset;
-                let pos_z = z-count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_3 = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while 3
-
-            count = 1.;
-            while flag_4{
-                let pos_x = x-count*offset;
-                let pos_z = z-count*offset;
-                let pos = chess_pos(pos_x,pos_z);
-                if db.data.contains_key(&pos) || pos_z.abs() > 24. {
-                    flag_4 = false;
-                }
-                else{
-                    returns.push((pos_x,pos_z));
-                    count+=1.;
-                }
-            }//while 4
+        mover = mover.opposite();
+    }
+    if !record.moves.is_empty(){
+        clock.start(mover);
+    }
+    clock
+}
 
+//the Polyglot book the computer and the book window draw on
+#[derive(Resource)]
+struct OpeningBook{
+    path:String,
+    book:Option<Book>,
+    //why there is no book, or how big it is
+    status:Option<String>,
+}
 
+impl Default for OpeningBook{
+    fn default()->OpeningBook{
+        OpeningBook{path:"assets/book.bin".to_string(),book:None,status:None}
+    }
+}
+
+impl OpeningBook{
+    fn load(&mut self){
+        match Book::open(&self.path){
+            Ok(book) => {
+                self.status = Some(format!("{} entries",book.len()));
+                self.book = Some(book);
+            }
+            Err(error) => {
+                self.status = Some(error);
+                self.book = None;
+            }
         }
-        if self.tag == "knight".to_string(){
-            returns.push((x+offset*1.,z+offset*2.));
-            returns.push((x-offset*1.,z+offset*2.));
-            returns.push((x+offset*1.,z-offset*2.));
-            returns.push((x-offset*1.,z-offset*2.));
-            returns.push((x+offset*2.,z+offset*1.));
-            returns.push((x-offset*2.,z+offset*1.));
-            returns.push((x+offset*2.,z-offset*1.));
-            returns.push((x-offset*2.,z-offset*1.));
+    }
+}
+
+//Syzygy endgame tables from `directory`, for the analysis window and the computer
+#[derive(Resource)]
+struct Tablebases{
+    directory:String,
+    tablebase:Option<Arc<Tablebase>>,
+    //the tables being opened, every file's header gets read
+    loading:Option<Background<Result<Tablebase,String>>>,
+    status:Option<String>,
+    //what the tables said about the positions looked at so far, by zobrist key
+    analyses:HashMap<u64,TablebaseAnalysis>,
+    //the position being probed right now, by zobrist key
+    probing:Option<(u64,Background<TablebaseAnalysis>)>,
+}
+
+impl Default for Tablebases{
+    fn default()->Tablebases{
+        Tablebases{
+            directory:"syzygy".to_string(),
+            tablebase:None,
+            loading:None,
+            status:None,
+            analyses:HashMap::new(),
+            probing:None,
         }
+    }
+}
 
+impl Tablebases{
+    //the tables in use stay until the new ones are open, see poll
+    fn load(&mut self){
+        let directory = self.directory.clone();
+        self.loading = Some(Background::spawn(move || Tablebase::open(&directory)));
+        self.status = Some("loading...".to_string());
+    }
 
-        //illegal move filter add here
-        let mut returns_filtered = Vec::new();
-        for i in returns{
-            let x = i.0;
-            let z = i.1;
-            let pos = chess_pos(x,z);
-            if !(db.data.contains_key(&pos) || x.abs()>21. || z.abs()>21.){
-                returns_filtered.push(i);
+    //picks up the tables once they are open
+    fn poll(&mut self){
+        let loaded = match self.loading.as_ref().and_then(|loading| loading.take()){
+            Some(loaded) => loaded,
+            None => return,
+        };
+        self.loading = None;
+        self.analyses.clear();
+        self.probing = None;
+        match loaded{
+            Ok(tablebase) => {
+                self.status = Some(format!("{} tables, up to {} pieces{}",
+                    tablebase.len(),tablebase.max_pieces(),if tablebase.has_dtz() {""} else {", no DTZ"}));
+                self.tablebase = Some(Arc::new(tablebase));
+            }
+            Err(error) => {
+                self.status = Some(error);
+                self.tablebase = None;
             }
         }
-        return returns_filtered
     }
 
+    //what the tables say about position, None until the probe is back;
+    //the probe runs on its own thread, one position at a time
+    fn analysis(&mut self,position:&Position)->Option<&TablebaseAnalysis>{
+        let tablebase = self.tablebase.clone()?;
+        let key = position.zobrist();
+        let probed = self.probing.as_ref().and_then(|(probed,probing)| Some((*probed,probing.take()?)));
+        if let Some((probed,analysis)) = probed{
+            self.analyses.insert(probed,analysis);
+            self.probing = None;
+        }
+        //the board moved on, a probe of another position is not waited for
+        if !self.analyses.contains_key(&key) && self.probing.as_ref().map(|(probed,_)| *probed) != Some(key){
+            let position = *position;
+            self.probing = Some((key,Background::spawn(move || {
+                let mut moves = tablebase.probe_moves(&position).unwrap_or_default();
+                moves.sort_by_key(|probe| (std::cmp::Reverse(probe.wdl),probe.dtz.unwrap_or(i32::MAX)));
+                TablebaseAnalysis{
+                    wdl:tablebase.probe_wdl(&position),
+                    dtz:tablebase.probe_dtz(&position),
+                    moves,
+                }
+            })));
+        }
+        self.analyses.get(&key)
+    }
+}
+
+struct TablebaseAnalysis{
+    wdl:Option<Wdl>,
+    dtz:Option<i32>,
+    //best first
+    moves:Vec<MoveProbe>,
+}
+
+//a search running on its own thread, built-in or external; it leaves the
+//move (or why there is none) in `result`
+struct Thinking{
+    position:Position,
+    stop:Arc<AtomicBool>,
+    result:Arc<Mutex<Option<Result<Move,String>>>>,
+}
+
+//slow work (starting an engine, opening tables) done on its own thread so
+//the frame goes on; a system picks the result up with take once it is there
+struct Background<T>(Arc<Mutex<Option<T>>>);
+
+impl<T:Send+'static> Background<T>{
+    fn spawn(work:impl FnOnce()->T+Send+'static)->Background<T>{
+        let result = Arc::new(Mutex::new(None));
+        let thread_result = result.clone();
+        thread::spawn(move || {
+            let done = work();
+            *thread_result.lock().unwrap() = Some(done);
+        });
+        Background(result)
+    }
+
+    fn take(&self)->Option<T>{
+        self.0.lock().unwrap().take()
+    }
+}
+
+//sets the game up from this record, its moves included (new game button,
+//FEN import, PGN replay)
+struct LoadGame(PgnGame);
 
+//plays a legal move on the board and the entities, see play_move_script
+struct PlayMove(Move);
 
+//text typed into the FEN window and why it was rejected
+#[derive(Resource)]
+#[derive(Default)]
+struct FenPanel{
+    input:String,
+    error:Option<String>,
+}
+
+impl Piece{
+    fn new(tag:String,color:PieceColor)->Piece{
+        Piece { 
+            tag,
+            color,
+            move_count:0
+                
+        }
+    }
+
+    fn from_board(piece:&board::Piece)->Piece{
+        Piece::new(piece.kind.name().to_string(),piece.color)
+    }
 }
 
 #[derive(Resource)]
@@ -415,10 +459,93 @@ impl Controller{
 
 
 
+//bookkeeping around the position: promotions, results and draw claims
 #[derive(Resource)]
 #[derive(Debug)]
+struct GameState{
+    //move of a pawn to the last rank waiting for the player's pick; it is only
+    //played once the piece is chosen
+    promotion:Option<Move>,
+    //set when the turn passes, game_status_script looks for mate/stalemate then
+    status_pending:bool,
+    result:Option<GameResult>,
+    //position after every move, the starting position included; the current
+    //one is added by game_status_script
+    position_history:Vec<Position>,
+    //draw the side to move may claim (threefold repetition, fifty-move rule)
+    claimable_draw:Option<String>,
+    //every move played so far, for the PGN export
+    record:PgnGame,
+    //the moves played on this board with the pieces they took, for ctrl+z and ctrl+y
+    history:History<Piece>,
+    //plies still to take back; undo_redo_script does one a frame, so a piece
+    //it brought back is on the board before the next ply moves it
+    take_back:usize,
+}
+
+#[derive(Debug,Clone)]
+struct GameResult{
+    //None for a draw
+    winner:Option<PieceColor>,
+    reason:String,
+}
+
+impl GameResult{
+    fn describe(&self)->String{
+        match self.winner{
+            Some(PieceColor::White) => format!("White wins by {}",self.reason),
+            Some(PieceColor::Black) => format!("Black wins by {}",self.reason),
+            None => format!("Draw by {}",self.reason),
+        }
+    }
+
+    //result token for the PGN export
+    fn pgn_result(&self)->&'static str{
+        match self.winner{
+            Some(PieceColor::White) => "1-0",
+            Some(PieceColor::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
+
+impl GameState{
+    fn new(start:Position)->GameState{
+        GameState::from_record(PgnGame::new(start))
+    }
+
+    //continues a recorded game after its last move
+    fn from_record(record:PgnGame)->GameState{
+        GameState{
+            promotion:None,
+            //the current position goes into the history on the first frame
+            status_pending:true,
+            result:None,
+            position_history:(0..record.moves.len()).map(|ply| record.position_at(ply)).collect(),
+            claimable_draw:None,
+            record,
+            history:History::new(),
+            take_back:0,
+        }
+    }
+
+    fn finish(&mut self,result:GameResult){
+        self.record.set_result(result.pgn_result());
+        self.result = Some(result);
+    }
+}
+
+//the rules live in chess::board, the entities on screen follow this position
+#[derive(Resource)]
+#[derive(Debug,Clone)]
 struct BoardDataBase{
-    data:HashMap<String,String>
+    position:Position,
+}
+
+impl BoardDataBase{
+    fn new()->BoardDataBase{
+        BoardDataBase{position:Position::startpos()}
+    }
 }
 
 fn asset_loading(mut commands: Commands, assets: Res<AssetServer>) {
@@ -454,8 +581,10 @@ fn asset_loading(mut commands: Commands, assets: Res<AssetServer>) {
     };
     let mv_db = MovementDataBase{data:HashMap::new(),piece_id:"".to_string()};
     let controller = Controller::new();
-    let bd_db = BoardDataBase{data:HashMap::new()};
+    let bd_db = BoardDataBase::new();
+    let game_state = GameState::new(Position::startpos());
     commands.insert_resource(mv_db);
+    commands.insert_resource(game_state);
     commands.insert_resource(controller);
     commands.insert_resource(bd_db);
     commands.insert_resource(chess_board);
@@ -468,9 +597,6 @@ pub fn spawn_basic_chess_board(
     mut meshes: ResMut<Assets<Mesh>>,
     chess_board:Res<ChessBoard>){
 
-    let default_collider_color = materials.add(Color::rgba(0.3, 0.5, 0.3, 0.1).into());
-    let selected_collider_color = materials.add(Color::rgba(0.3, 0.9, 0.3, 0.3).into());
-
     commands.spawn(
         SceneBundle{
             scene:chess_board.board.clone(),
@@ -481,269 +607,7 @@ pub fn spawn_basic_chess_board(
     )
         .insert(Name::new("chess board"));
 
-    commands
-        .spawn(SpatialBundle::from_transform(
-                Transform::from_xyz(-3., 0., 21.,)
-                .with_scale(Vec3::new(3., 18., 3.))
-        ))
-        .insert(Name::new("BLACK KING"))
-        .insert(Piece::new("king".to_string()))
-        .insert(meshes.add(shape::Cube::default().into()))
-        .insert(Highlighting {
-            initial: default_collider_color.clone(),
-            hovered: Some(selected_collider_color.clone()),
-            pressed: Some(selected_collider_color.clone()),
-            selected: Some(selected_collider_color.clone()),
-        })
-    .insert(default_collider_color.clone())
-        .insert(PickableBundle::default())
-        .with_children(|commands| {
-            commands.spawn(
-                SceneBundle{
-                    scene:chess_board.bking.clone(),
-                    transform:Transform::from_xyz(0., -0., 0.)
-                        .with_scale(Vec3::new(1./3.,1./18.,1./3.)),
-                        ..Default::default()
-
-                })
-            .insert(Name::new("black king"));
-
-        }); 
-
-    commands
-        .spawn(SpatialBundle::from_transform(
-                Transform::from_xyz(3., 0., 21.,)
-                .with_scale(Vec3::new(3., 15., 3.))
-        ))
-        .insert(Name::new("bqueen"))
-        .insert(Piece::new("queen".to_string()))
-        .insert(meshes.add(shape::Cube::default().into()))
-        .insert(Highlighting {
-            initial: default_collider_color.clone(),
-            hovered: Some(selected_collider_color.clone()),
-            pressed: Some(selected_collider_color.clone()),
-            selected: Some(selected_collider_color.clone()),
-        })
-    .insert(default_collider_color.clone())
-        .insert(PickableBundle::default())
-        .with_children(|commands| {
-            commands.spawn(
-                SceneBundle{
-                    scene:chess_board.bqueen.clone(),
-                    transform:Transform::from_xyz(0., -0., 0.)
-                        .with_scale(Vec3::new(1./3.,1./15.,1./3.)),
-                        ..Default::default()
-
-                })
-            .insert(Name::new("black queen"));
-
-        }); 
-
-
-    commands
-        .spawn(SpatialBundle::from_transform(
-                Transform::from_xyz(9., 0., 21.,)
-                .with_scale(Vec3::new(3., 14., 3.))
-        ))
-        .insert(Name::new("bbsishop"))
-        .insert(Piece::new("bishop".to_string()))
-        .insert(meshes.add(shape::Cube::default().into()))
-        .insert(Highlighting {
-            initial: default_collider_color.clone(),
-            hovered: Some(selected_collider_color.clone()),
-            pressed: Some(selected_collider_color.clone()),
-            selected: Some(selected_collider_color.clone()),
-        })
-    .insert(default_collider_color.clone())
-        .insert(PickableBundle::default())
-        .with_children(|commands| {
-            commands.spawn(
-                SceneBundle{
-                    scene:chess_board.bbishop.clone(),
-                    transform:Transform::from_xyz(0., -0., 0.)
-                        .with_scale(Vec3::new(1./3.,1./14.,1./3.)),
-                        ..Default::default()
-
-                })
-            .insert(Name::new("black bishop"));
-
-        }); 
-
-    commands
-        .spawn(SpatialBundle::from_transform(
-                Transform::from_xyz(-9., 0., 21.,)
-                .with_scale(Vec3::new(3., 14., 3.))
-        ))
-        .insert(Name::new("bbishop"))
-        .insert(Piece::new("bishop".to_string()))
-        .insert(meshes.add(shape::Cube::default().into()))
-        .insert(Highlighting {
-            initial: default_collider_color.clone(),
-            hovered: Some(selected_collider_color.clone()),
-            pressed: Some(selected_collider_color.clone()),
-            selected: Some(selected_collider_color.clone()),
-        })
-    .insert(default_collider_color.clone())
-        .insert(PickableBundle::default())
-        .with_children(|commands| {
-            commands.spawn(
-                SceneBundle{
-                    scene:chess_board.bbishop.clone(),
-                    transform:Transform::from_xyz(0., -0., 0.)
-                        .with_scale(Vec3::new(1./3.,1./14.,1./3.)),
-                        ..Default::default()
-
-                })
-            .insert(Name::new("black bishop"));
-
-        }); 
-
-
-    commands
-        .spawn(SpatialBundle::from_transform(
-                Transform::from_xyz(-15., 0., 21.,)
-                .with_scale(Vec3::new(3., 10., 3.))
-        ))
-        .insert(Name::new("BLACK KNIGHT"))
-        .insert(Piece::new("knight".to_string()))
-        .insert(meshes.add(shape::Cube::default().into()))
-        .insert(Highlighting {
-            initial: default_collider_color.clone(),
-            hovered: Some(selected_collider_color.clone()),
-            pressed: Some(selected_collider_color.clone()),
-            selected: Some(selected_collider_color.clone()),
-        })
-    .insert(default_collider_color.clone())
-        .insert(PickableBundle::default())
-        .with_children(|commands| {
-            commands.spawn(
-                SceneBundle{
-                    scene:chess_board.bknight.clone(),
-                    transform:Transform::from_xyz(0., -0., 0.)
-                        .with_scale(Vec3::new(1./3.,1./10.,1./3.))
-                        .with_rotation(Quat::from_rotation_y(1.5)),
-                        ..Default::default()
-
-                })
-            .insert(Name::new("black knight"));
-
-        }); 
-
-    commands
-        .spawn(SpatialBundle::from_transform(
-                Transform::from_xyz(15., 0., 21.,)
-                .with_scale(Vec3::new(3., 10., 3.))
-        ))
-        .insert(Name::new("bknight"))
-        .insert(Piece::new("knight".to_string()))
-        .insert(meshes.add(shape::Cube::default().into()))
-        .insert(Highlighting {
-            initial: default_collider_color.clone(),
-            hovered: Some(selected_collider_color.clone()),
-            pressed: Some(selected_collider_color.clone()),
-            selected: Some(selected_collider_color.clone()),
-        })
-    .insert(default_collider_color.clone())
-        .insert(PickableBundle::default())
-        .with_children(|commands| {
-            commands.spawn(
-                SceneBundle{
-                    scene:chess_board.bknight.clone(),
-                    transform:Transform::from_xyz(0., -0., 0.)
-                        .with_scale(Vec3::new(1./3.,1./10.,1./3.))
-                        .with_rotation(Quat::from_rotation_y(1.5)),
-                        ..Default::default()
-
-                })
-            .insert(Name::new("black knight"));
-        }); 
-
-    commands
-        .spawn(SpatialBundle::from_transform(
-                Transform::from_xyz(21., 0., 21.,)
-                .with_scale(Vec3::new(3., 10., 3.))
-        ))
-        .insert(Name::new("brook"))
-        .insert(meshes.add(shape::Cube::default().into()))
-        .insert(Highlighting {
-            initial: default_collider_color.clone(),
-            hovered: Some(selected_collider_color.clone()),
-            pressed: Some(selected_collider_color.clone()),
-            selected: Some(selected_collider_color.clone()),
-        })
-        .insert(default_collider_color.clone())
-        .insert(Piece::new("rook".to_string()))
-        .insert(PickableBundle::default())
-        .with_children(|commands| {
-            commands.spawn(
-                SceneBundle{
-                    scene:chess_board.brook.clone(),
-                    transform:Transform::from_xyz(0., -0., 0.)
-                        .with_scale(Vec3::new(1./3.,1./10.,1./3.))
-                        .with_rotation(Quat::from_rotation_y(1.5)),
-                        ..Default::default()
-
-                })
-            .insert(Name::new("black rook"));
-        }); 
-
-    commands
-        .spawn(SpatialBundle::from_transform(
-                Transform::from_xyz(-21., 0., 21.,)
-                .with_scale(Vec3::new(3., 10., 3.))
-        ))
-        .insert(Name::new("BLACK ROOK"))
-        .insert(meshes.add(shape::Cube::default().into()))
-        .insert(Highlighting {
-            initial: default_collider_color.clone(),
-            hovered: Some(selected_collider_color.clone()),
-            pressed: Some(selected_collider_color.clone()),
-            selected: Some(selected_collider_color.clone()),
-        })
-        .insert(default_collider_color.clone())
-        .insert(Piece::new("rook".to_string()))
-        .insert(PickableBundle::default())
-        .with_children(|commands| {
-            commands.spawn(
-                SceneBundle{
-                    scene:chess_board.brook.clone(),
-                    transform:Transform::from_xyz(0., -0., 0.)
-                        .with_scale(Vec3::new(1./3.,1./10.,1./3.))
-                        .with_rotation(Quat::from_rotation_y(1.5)),
-                        ..Default::default()
-                })
-            .insert(Name::new("black rook"));
-        });
-
-    for i in 0..8{
-    commands
-        .spawn(SpatialBundle::from_transform(
-                Transform::from_xyz(-21.+i as f32*6., 0., 15.,)
-                .with_scale(Vec3::new(3., 10., 3.))
-        ))
-        .insert(Name::new("bpawn"))
-        .insert(Piece::new("pawn".to_string()))
-        .insert(meshes.add(shape::Cube::default().into()))
-        .insert(Highlighting {
-            initial: default_collider_color.clone(),
-            hovered: Some(selected_collider_color.clone()),
-            pressed: Some(selected_collider_color.clone()),
-            selected: Some(selected_collider_color.clone()),
-        })
-        .insert(default_collider_color.clone())
-        .insert(PickableBundle::default())
-        .with_children(|commands| {
-            commands.spawn(
-                SceneBundle{
-                    scene:chess_board.bpawn.clone(),
-                    transform:Transform::from_xyz(0., -0., 0.)
-                        .with_scale(Vec3::new(1./3.,1./10.,1./3.))
-                        .with_rotation(Quat::from_rotation_y(1.5)),
-                        ..Default::default()
-                })
-            .insert(Name::new("black pawn"));
-        });
-    }
+    spawn_pieces(&mut commands, &mut materials, &mut meshes, &chess_board, &Position::startpos());
 
     //tiles
     // for i in 0..8{
@@ -760,96 +624,105 @@ pub fn spawn_basic_chess_board(
     //                 hovered: Some(selected_collider_color.clone()),
     //                 pressed: Some(selected_collider_color.clone()),
     //                 selected: Some(selected_collider_color.clone()),
-    //             })
-    //         .insert(default_collider_color.clone())
-    //             .insert(PickableBundle::default());
-
-    //     }
-    // }
-    //white pieces
-    commands.spawn(
-        SceneBundle{
-            scene:chess_board.wking.clone(),
-            transform:Transform::from_xyz(-3., 0., -21.),
-            ..Default::default()
-
-        })
-    .insert(Name::new("white king"));
-
-    commands.spawn(
-        SceneBundle{
-            scene:chess_board.wqueen.clone(),
-            transform:Transform::from_xyz(3., 0., -21.),
-            ..Default::default()
-
-        })
-    .insert(Name::new("white queen"));
-
-    commands.spawn(
-        SceneBundle{
-            scene:chess_board.wbishop.clone(),
-            transform:Transform::from_xyz(9., 0., -21.),
-            ..Default::default()
-
-        })
-    .insert(Name::new("white bishop"));
-
-    commands.spawn(
-        SceneBundle{
-            scene:chess_board.wbishop.clone(),
-            transform:Transform::from_xyz(-9., 0., -21.),
-            ..Default::default()
-
-        })
-    .insert(Name::new("white bishop"));
+    //             })
+    //         .insert(default_collider_color.clone())
+    //             .insert(PickableBundle::default());
 
-    commands.spawn(
-        SceneBundle{
-            scene:chess_board.wknight.clone(),
-            transform:Transform::from_xyz(15., 0., -21.)
-                .with_rotation(Quat::from_rotation_y(-1.5)),
-                ..Default::default()
+    //     }
+    // }
+}
 
-        })
-    .insert(Name::new("white knight"));
+//one entity per piece standing in `position`
+fn spawn_pieces(
+    commands:&mut Commands,
+    materials:&mut Assets<StandardMaterial>,
+    meshes:&mut Assets<Mesh>,
+    chess_board:&ChessBoard,
+    position:&Position){
+
+    let colliders = collider_materials(materials);
+    for (sq,piece) in position.pieces(){
+        spawn_piece(commands, meshes, chess_board, &colliders,
+            Piece::from_board(&piece), sq);
+    }
+}
 
-    commands.spawn(
-        SceneBundle{
-            scene:chess_board.wknight.clone(),
-            transform:Transform::from_xyz(-15., 0., -21.)
-                .with_rotation(Quat::from_rotation_y(1.5)),
-                ..Default::default()
+//default and highlighted look of the piece colliders
+fn collider_materials(materials:&mut Assets<StandardMaterial>)->(Handle<StandardMaterial>,Handle<StandardMaterial>){
+    let default_collider_color = materials.add(Color::rgba(0.3, 0.5, 0.3, 0.1).into());
+    let selected_collider_color = materials.add(Color::rgba(0.3, 0.9, 0.3, 0.3).into());
+    (default_collider_color,selected_collider_color)
+}
 
-        })
-    .insert(Name::new("white knight"));
+//spawns a pickable collider carrying the Piece with its model as a child scene
+fn spawn_piece(
+    commands:&mut Commands,
+    meshes:&mut Assets<Mesh>,
+    chess_board:&ChessBoard,
+    colliders:&(Handle<StandardMaterial>,Handle<StandardMaterial>),
+    piece:Piece,
+    sq:board::Square){
 
-    commands.spawn(
-        SceneBundle{
-            scene:chess_board.wrook.clone(),
-            transform:Transform::from_xyz(21., 0., -21.),
-            ..Default::default()
+    let (default_collider_color,selected_collider_color) = colliders;
+    let height = collider_height(&piece.tag);
+    let model = piece_model(chess_board,&piece,sq);
+    let name = piece_name(&piece);
 
+    commands
+        .spawn(SpatialBundle::from_transform(
+                Transform::from_translation(square_translation(sq))
+                .with_scale(Vec3::new(3., height, 3.))
+        ))
+        .insert(Name::new(name.clone()))
+        .insert(piece)
+        .insert(meshes.add(shape::Cube::default().into()))
+        .insert(Highlighting {
+            initial: default_collider_color.clone(),
+            hovered: Some(selected_collider_color.clone()),
+            pressed: Some(selected_collider_color.clone()),
+            selected: Some(selected_collider_color.clone()),
         })
-    .insert(Name::new("white rook"));
+        .insert(default_collider_color.clone())
+        .insert(PickableBundle::default())
+        .with_children(|commands| {
+            commands.spawn(model)
+            .insert(Name::new(format!("{} model",name)));
+        });
+}
 
-    commands.spawn(
-        SceneBundle{
-            scene:chess_board.wrook.clone(),
-            transform:Transform::from_xyz(-21., 0., -21.),
+//the model scene sits under the collider, so it undoes the collider's scale
+fn piece_model(chess_board:&ChessBoard,piece:&Piece,sq:board::Square)->SceneBundle{
+    let height = collider_height(&piece.tag);
+    //knights face the centre of the board, the rest keep the model orientation
+    let rotation = match (piece.tag.as_str(),&piece.color){
+        ("knight",PieceColor::White) => if sq.file() < 4 {-1.5} else {1.5},
+        ("knight",PieceColor::Black) | ("rook",PieceColor::Black) | ("pawn",PieceColor::Black) => 1.5,
+        _ => 0.,
+    };
+    SceneBundle{
+        scene:chess_board.scene(&piece.tag,&piece.color),
+        transform:Transform::from_xyz(0., -0., 0.)
+            .with_scale(Vec3::new(1./3.,1./height,1./3.))
+            .with_rotation(Quat::from_rotation_y(rotation)),
             ..Default::default()
-        })
-    .insert(Name::new("white rook"));
+    }
+}
 
-    for i in 0..8{
-        commands.spawn(
-            SceneBundle{
-                scene:chess_board.wpawn.clone(),
-                transform:Transform::from_xyz(-21.+i as f32*6., 0., -15.),
-                ..Default::default()
-            })
-        .insert(Name::new("white pawn"));
+//collider heights roughly follow the models
+fn collider_height(tag:&str)->f32{
+    match tag{
+        "king" => 18.,
+        "queen" => 15.,
+        "bishop" => 14.,
+        _ => 10.,
     }
+}
 
+fn piece_name(piece:&Piece)->String{
+    match piece.color{
+        PieceColor::White => format!("white {}",piece.tag),
+        PieceColor::Black => format!("black {}",piece.tag),
+    }
 }
 
 
@@ -876,6 +749,16 @@ fn spawn_camera(mut commands: Commands) {
     .insert_bundle(PickingCameraBundle::default());
 }
 
+//a missing book is fine, the computer searches from the first move then
+fn load_opening_book(mut book:ResMut<OpeningBook>){
+    book.load();
+}
+
+//same for the tablebases, they only help in the endgame
+fn load_tablebases(mut tables:ResMut<Tablebases>){
+    tables.load();
+}
+
 
 
 fn camera_controls(
@@ -948,27 +831,37 @@ fn chess_data_piece(
     mut selection:Query<(&Selection,&mut Transform,Entity,&Piece)>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut b_db:ResMut<BoardDataBase>,
+    b_db:Res<BoardDataBase>,
     mut ct:ResMut<Controller>,
+    gs:Res<GameState>,
+    ai:Res<AiPlayer>,
     keyboard:Res<Input<KeyCode>>){
 
     let default_collider_color = materials.add(Color::rgba(0.0, 0.3, 0.3, 1.).into());
     let selected_collider_color = materials.add(Color::rgba(0.0, 0.9, 0.3, 1.).into());
     for (selection,mut transform,entity,piece) in selection.iter_mut(){
-        if selection.selected() && ct.spawn_square_flag{
+        //only the side to move gets target squares, and not while a promotion is pending,
+        //once the game is over or when the computer plays that side
+        if selection.selected() && ct.spawn_square_flag && piece.color == b_db.position.side_to_move()
+            && gs.promotion.is_none() && gs.result.is_none() && ai.color != Some(piece.color){
             db.data.insert("piece".to_string(),transform.clone());
             db.piece_id = format!("{:?}",entity);
             ct.piece_flag=true;
 
-                let x = transform.translation.x;
-                let z = transform.translation.z;
-                let offset = -6.;
-                let a = piece.possible_moves(&x,&z, offset, &b_db);
+                let mut a = Vec::new();
+                if let Some(from) = translation_square(transform.translation){
+                    //the four promotion choices share one target square
+                    for mv in b_db.position.legal_moves_from(from){
+                        if !a.contains(&mv.to){
+                            a.push(mv.to);
+                        }
+                    }
+                }
                 for i in a{
                     commands
                         .spawn(SpatialBundle::from_transform(
-                                Transform::from_xyz(i.0, 0. , i.1,)
-                                .with_scale(Vec3::new(6., 0.05, 6.))
+                                Transform::from_translation(square_translation(i))
+                                .with_scale(Vec3::new(SQUARE_SIZE, 0.05, SQUARE_SIZE))
                         ))
                         .insert(Name::new("chess_square"))
                         .insert(Square)
@@ -1006,20 +899,939 @@ fn chess_data_square(
 fn chess_movement_script(
     mut db:ResMut<MovementDataBase>,
     mut ct:ResMut<Controller>,
-    mut selection:Query<(&Selection,&mut Transform,Entity,&mut Piece)>,
+    mut gs:ResMut<GameState>,
+    b_db:Res<BoardDataBase>,
+    mut play:EventWriter<PlayMove>,
     ){
     if ct.square_flag && ct.piece_flag{
-        for (selection,mut transform,entity,mut piece) in selection.iter_mut(){
-            if format!("{:?}",entity) == db.piece_id{
-                ct.piece_flag=false;
-                ct.square_flag=false;
-                let trans = db.data[&"square".to_string()].translation;
-                transform.translation = trans;
-                ct.purge_square_flag = true;
+        let trans = db.data[&"square".to_string()].translation;
+        let from = db.data[&"piece".to_string()].translation;
+        ct.piece_flag=false;
+        ct.square_flag=false;
+        ct.purge_square_flag = true;
+        let mv = match (translation_square(from),translation_square(trans)){
+            (Some(from),Some(to)) => b_db.position.legal_moves_from(from).into_iter().find(|mv| mv.to == to),
+            _ => None,
+        };
+        let mv = match mv{
+            Some(mv) => mv,
+            None => return,
+        };
+        //a pawn going to the last rank waits for promotion_picker
+        if mv.promotion.is_some(){
+            gs.promotion = Some(mv);
+        }
+        else{
+            play.send(PlayMove(mv));
+        }
+    }
+}
+
+//lets the player pick what a pawn on the last rank becomes
+fn promotion_picker(
+    mut egui_context:ResMut<EguiContext>,
+    mut gs:ResMut<GameState>,
+    mut play:EventWriter<PlayMove>,
+    ){
+    let mv = match gs.promotion{
+        Some(mv) => mv,
+        None => return,
+    };
+    let mut choice = None;
+    egui::Window::new("Promotion")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label("Promote pawn to:");
+            ui.horizontal(|ui| {
+                for kind in PieceKind::PROMOTIONS{
+                    if ui.button(kind.name()).clicked(){
+                        choice = Some(kind);
+                    }
+                }
+            });
+        });
+
+    if let Some(kind) = choice{
+        gs.promotion = None;
+        play.send(PlayMove(Move{promotion:Some(kind),..mv}));
+    }
+}
+
+//the one place moves get executed, whether picked on the board or replayed:
+//moves the entities along, plays the move on the position and records it
+fn play_move_script(
+    mut commands:Commands,
+    mut events:EventReader<PlayMove>,
+    chess_board:Res<ChessBoard>,
+    mut gs:ResMut<GameState>,
+    mut b_db:ResMut<BoardDataBase>,
+    mut clock:ResMut<GameClock>,
+    mut query:Query<(Entity,&mut Piece,&mut Transform,&mut Name,&Children)>,
+    ){
+    //despawning waits for the end of the frame, so captured entities are still in the query
+    let mut captured_entities = Vec::new();
+    for PlayMove(mv) in events.iter(){
+        let position = b_db.position;
+        if gs.result.is_some() || !position.is_legal(mv){
+            continue
+        }
+        //capture: whatever stands on the captured square goes away (with its scene child),
+        //for en passant that is next to the target rather than on it
+        let captured = position.captured_square(mv);
+        //castling: the rook moves over to the square the king passed
+        let castling_rook = position.castling_rook(mv);
+
+        let mut mover = None;
+        let mut rook = None;
+        let mut captured_piece = None;
+        for (entity,piece,transform,_,_) in query.iter(){
+            if captured_entities.contains(&entity){
+                continue
+            }
+            let pos = translation_square(transform.translation);
+            if pos == Some(mv.from){
+                mover = Some(entity);
+            }
+            else if pos.is_some() && pos == captured{
+                commands.entity(entity).despawn_recursive();
+                captured_entities.push(entity);
+                captured_piece = Some(piece.clone());
+            }
+            else if pos.is_some() && pos == castling_rook.map(|(rook_from,_)| rook_from){
+                rook = Some(entity);
+            }
+        }
+        if let (Some(entity),Some((_,rook_to))) = (rook,castling_rook){
+            if let Ok((_,mut piece,mut transform,_,_)) = query.get_mut(entity){
+                transform.translation = square_translation(rook_to);
+                piece.move_count+=1;
+            }
+        }
+        if let Some(entity) = mover{
+            if let Ok((entity,mut piece,mut transform,mut name,children)) = query.get_mut(entity){
+                transform.translation = square_translation(mv.to);
                 piece.move_count+=1;
+                if let Some(kind) = mv.promotion{
+                    set_piece_kind(&mut commands,&chess_board,entity,&mut piece,&mut transform,&mut name,children,kind,mv.to);
+                }
+            }
+        }
+
+        //the captured piece's component keeps its move_count for the take-back
+        gs.history.push(&position,*mv,captured_piece);
+        let reading = clock.clock.as_mut().map(|clock| clock.press(position.side_to_move()));
+        gs.record.push(*mv,reading);
+        b_db.position.play(mv);
+        gs.status_pending = true;
+    }
+}
+
+//starts a search when it is the computer's turn and plays the move it finds
+fn ai_script(
+    gs:Res<GameState>,
+    b_db:Res<BoardDataBase>,
+    mut ai:ResMut<AiPlayer>,
+    book:Res<OpeningBook>,
+    tables:Res<Tablebases>,
+    clock:Res<GameClock>,
+    mut play:EventWriter<PlayMove>,
+    ){
+    let position = b_db.position;
+    let its_turn = ai.color == Some(position.side_to_move()) && gs.result.is_none();
+    if let Some(thinking) = &ai.thinking{
+        //the board changed under the search (undo, new game, side switched)
+        if thinking.position != position || !its_turn{
+            thinking.stop.store(true,Ordering::Relaxed);
+            ai.thinking = None;
+            return
+        }
+        let found = thinking.result.lock().unwrap().take();
+        match found{
+            Some(Ok(mv)) => {
+                ai.thinking = None;
+                play.send(PlayMove(mv));
+            }
+            //a crashed or confused engine is let go, the built-in search
+            //picks the move up on the next frame
+            Some(Err(error)) => {
+                ai.thinking = None;
+                ai.engine = None;
+                ai.status = Some(format!("{}; the built-in search plays on",error));
+            }
+            None => {}
+        }
+        return
+    }
+    //wait for game_status_script so a finished game is not searched, and for
+    //a take-back to finish
+    if !its_turn || gs.promotion.is_some() || gs.status_pending || gs.take_back > 0{
+        return
+    }
+
+    //no search while the game is still in the book
+    if let (Some(choice),Some(book)) = (ai.book,&book.book){
+        let random = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+        if let Some(mv) = book.pick(&position,choice,random){
+            play.send(PlayMove(mv));
+            return
+        }
+    }
+
+    let (_,depth,millis) = search::LEVELS[ai.level];
+    let mut movetime = Duration::from_millis(millis);
+    //on the clock the level's time is only an upper bound; a delay or a
+    //Bronstein bonus is worth at most an increment of the same size
+    if let Some(clock) = &clock.clock{
+        let remaining = clock.remaining(position.side_to_move());
+        movetime = movetime.min(search::time_for_move(remaining,clock.control().bonus,None));
+    }
+    let limits = Limits{depth,movetime:Some(movetime),nodes:None};
+    let stop = Arc::new(AtomicBool::new(false));
+    let result = Arc::new(Mutex::new(None));
+    let (thread_stop,thread_result) = (stop.clone(),result.clone());
+    //an engine that died between moves is let go before it is asked for one
+    let engine = ai.engine.clone();
+    if let Some(engine) = &engine{
+        //still winding down a search that was called off, next frame then
+        let mut engine = match engine.try_lock(){
+            Ok(engine) => engine,
+            Err(_) => return,
+        };
+        if !engine.is_alive(){
+            ai.status = Some(format!("{} exited; the built-in search plays on",engine.name));
+            ai.engine = None;
+            return
+        }
+    }
+    let new_game = std::mem::take(&mut ai.new_game);
+    match engine{
+        Some(engine) => {
+            let start = gs.record.start;
+            let moves:Vec<Move> = gs.record.moves.iter().map(|record| record.mv).collect();
+            let limits = match &clock.clock{
+                Some(clock) => {
+                    let bonus = clock.control().bonus;
+                    EngineLimits::Clock{
+                        white:clock.remaining(PieceColor::White),
+                        black:clock.remaining(PieceColor::Black),
+                        white_increment:bonus,
+                        black_increment:bonus,
+                    }
+                }
+                None => EngineLimits::MoveTime(movetime),
+            };
+            thread::spawn(move || {
+                let mut engine = engine.lock().unwrap();
+                let ready = if new_game {engine.new_game()} else {Ok(())};
+                let found = ready.and_then(|_| engine.best_move(&start,&moves,limits,&thread_stop));
+                *thread_result.lock().unwrap() = Some(found);
+            });
+        }
+        None => {
+            let tablebase = tables.tablebase.clone();
+            thread::spawn(move || {
+                let info = search::search_with(&position,limits,&thread_stop,tablebase.as_deref(),|_| {});
+                *thread_result.lock().unwrap() = info.best_move().map(Ok);
+            });
+        }
+    }
+    ai.thinking = Some(Thinking{position,stop,result});
+}
+
+//which side the computer plays and at what level
+fn ai_panel(
+    mut egui_context:ResMut<EguiContext>,
+    mut ai:ResMut<AiPlayer>,
+    ){
+    let mut stop_thinking = false;
+    //the engine started in the background is through its handshake, it takes over
+    let started = ai.starting.as_ref().and_then(|starting| starting.take());
+    if let Some(started) = started{
+        ai.starting = None;
+        match started{
+            Ok(engine) => {
+                ai.status = Some(format!("{} is playing",engine.name));
+                ai.engine = Some(Arc::new(Mutex::new(engine)));
+                stop_thinking = true;
+            }
+            Err(error) => ai.status = Some(error),
+        }
+    }
+    egui::Window::new("Computer")
+        .anchor(egui::Align2::LEFT_TOP, [10., 10.])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("plays");
+                ui.radio_value(&mut ai.color,None,"nobody");
+                ui.radio_value(&mut ai.color,Some(PieceColor::White),"white");
+                ui.radio_value(&mut ai.color,Some(PieceColor::Black),"black");
+            });
+            ui.horizontal(|ui| {
+                ui.label("level");
+                for (i,(name,_,_)) in search::LEVELS.iter().enumerate(){
+                    ui.radio_value(&mut ai.level,i,*name);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("book moves");
+                ui.radio_value(&mut ai.book,None,"off");
+                ui.radio_value(&mut ai.book,Some(BookChoice::Best),"best");
+                ui.radio_value(&mut ai.book,Some(BookChoice::Weighted),"weighted");
+            });
+            if ai.thinking.is_some(){
+                ui.label("thinking...");
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("engine config");
+                ui.text_edit_singleline(&mut ai.config_path);
+            });
+            ui.horizontal(|ui| {
+                //whoever plays now goes on until the new engine is up
+                if ai.starting.is_some(){
+                    ui.label("starting engine...");
+                }
+                else if ui.button("Start engine").clicked(){
+                    let config_path = ai.config_path.clone();
+                    ai.starting = Some(Background::spawn(move || {
+                        EngineConfig::load(&config_path).and_then(|config| Engine::start(&config))
+                    }));
+                }
+                //an engine still starting is dropped once its thread is done
+                if (ai.engine.is_some() || ai.starting.is_some()) && ui.button("Stop engine").clicked(){
+                    ai.engine = None;
+                    ai.starting = None;
+                    ai.status = Some("the built-in search plays".to_string());
+                    stop_thinking = true;
+                }
+            });
+            if let Some(status) = &ai.status{
+                ui.label(status.as_str());
+            }
+            //the engine is busy while it thinks, its options can wait
+            let options = ai.engine.as_ref()
+                .and_then(|engine| engine.try_lock().ok().map(|engine| engine.options.clone()));
+            if let Some(options) = options{
+                ui.collapsing("engine options", |ui| {
+                    for option in options{
+                        ui.label(format!("{} ({}) default {}",option.name,option.kind,option.default.unwrap_or_default()));
+                    }
+                });
+            }
+        });
+    //a search already running belongs to whoever played before the switch
+    if stop_thinking{
+        if let Some(thinking) = ai.thinking.take(){
+            thinking.stop.store(true,Ordering::Relaxed);
+        }
+    }
+}
+
+//the opening book's moves in the current position, with their share of the weight
+fn book_panel(
+    mut egui_context:ResMut<EguiContext>,
+    mut book:ResMut<OpeningBook>,
+    b_db:Res<BoardDataBase>,
+    ){
+    let position = b_db.position;
+    egui::Window::new("Book")
+        .anchor(egui::Align2::RIGHT_TOP, [-10., 80.])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut book.path);
+                if ui.button("Load").clicked(){
+                    book.load();
+                }
+            });
+            if let Some(status) = &book.status{
+                ui.label(status.as_str());
+            }
+            if let Some(opened) = &book.book{
+                let moves = opened.moves(&position);
+                if moves.is_empty(){
+                    ui.label("out of book");
+                }
+                let total:u32 = moves.iter().map(|entry| entry.weight as u32).sum();
+                for entry in moves{
+                    let share = if total == 0 {0.0} else {entry.weight as f32*100.0/total as f32};
+                    ui.label(format!("{}  {:.1}%",position.san(&entry.mv),share));
+                }
+            }
+        });
+}
+
+//what the endgame tables say about the current position and each move from it
+fn tablebase_panel(
+    mut egui_context:ResMut<EguiContext>,
+    mut tables:ResMut<Tablebases>,
+    b_db:Res<BoardDataBase>,
+    ){
+    //tables loaded in the background take over here, the search picks them up from the resource
+    tables.poll();
+    let position = b_db.position;
+    egui::Window::new("Tablebase")
+        .anchor(egui::Align2::LEFT_TOP, [10., 60.])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut tables.directory);
+                if ui.button("Load").clicked(){
+                    tables.load();
+                }
+            });
+            if let Some(status) = &tables.status{
+                ui.label(status.as_str());
+            }
+            if tables.tablebase.is_none(){
+                return
+            }
+            let analysis = match tables.analysis(&position){
+                Some(analysis) => analysis,
+                None => {
+                    ui.label("probing...");
+                    return
+                }
+            };
+            match (analysis.wdl,analysis.dtz){
+                (Some(wdl),Some(dtz)) => ui.heading(format!("{}, dtz {}",wdl.name(),dtz)),
+                (Some(wdl),None) => ui.heading(wdl.name()),
+                (None,_) => ui.label("not in the tables"),
+            };
+            egui::ScrollArea::vertical().max_height(240.).show(ui, |ui| {
+                for probe in analysis.moves.iter(){
+                    let dtz = probe.dtz.map(|dtz| format!(", dtz {}",dtz)).unwrap_or_default();
+                    ui.label(format!("{}  {}{}",position.san(&probe.mv),probe.wdl.name(),dtz));
+                }
+            });
+        });
+}
+
+//ctrl+z takes the last move back, ctrl+y or ctrl+shift+z plays it again;
+//against the computer ctrl+z goes back to the player's last move
+fn undo_redo_script(
+    mut commands:Commands,
+    keyboard:Res<Input<KeyCode>>,
+    mut materials:ResMut<Assets<StandardMaterial>>,
+    mut meshes:ResMut<Assets<Mesh>>,
+    chess_board:Res<ChessBoard>,
+    mut gs:ResMut<GameState>,
+    mut b_db:ResMut<BoardDataBase>,
+    mut db:ResMut<MovementDataBase>,
+    mut ct:ResMut<Controller>,
+    mut clock:ResMut<GameClock>,
+    ai:Res<AiPlayer>,
+    mut play:EventWriter<PlayMove>,
+    mut query:Query<(Entity,&mut Piece,&mut Transform,&mut Name,&Children)>,
+    squares:Query<Entity,With<Square>>,
+    ){
+    //the rest of a take-back already under way goes first
+    if gs.take_back == 0{
+        let ctrl = keyboard.pressed(KeyCode::LControl) || keyboard.pressed(KeyCode::RControl);
+        let shift = keyboard.pressed(KeyCode::LShift) || keyboard.pressed(KeyCode::RShift);
+        if !ctrl{
+            return
+        }
+        if keyboard.just_pressed(KeyCode::Y) || (keyboard.just_pressed(KeyCode::Z) && shift){
+            if let (Some(mv),None) = (gs.history.redo(),gs.promotion){
+                play.send(PlayMove(mv));
+            }
+            return
+        }
+        if !keyboard.just_pressed(KeyCode::Z){
+            return
+        }
+        //a pawn waiting for its promotion has not moved yet
+        if gs.promotion.is_some(){
+            gs.promotion = None;
+            return
+        }
+        //with the player on move the computer's reply goes too, or it would
+        //just play it again; nothing happens before the player's first move
+        let plies = match ai.color{
+            Some(color) if color != b_db.position.side_to_move() => 2,
+            _ => 1,
+        };
+        if plies > gs.record.moves.len(){
+            return
+        }
+        gs.take_back = plies;
+    }
+    gs.take_back-=1;
+    let record = match gs.record.moves.pop(){
+        Some(record) => record,
+        None => return,
+    };
+    let mv = record.mv;
+    let ply = gs.record.moves.len();
+
+    let before = match gs.history.take_back(mv){
+        //the pieces are found on their squares, like play_move_script does:
+        //a captured piece put back earlier is a new entity
+        Some(taken_back) => {
+            for (entity,mut piece,mut transform,mut name,children) in query.iter_mut(){
+                let pos = translation_square(transform.translation);
+                if pos == Some(mv.to){
+                    transform.translation = square_translation(mv.from);
+                    piece.move_count-=1;
+                    if mv.promotion.is_some(){
+                        set_piece_kind(&mut commands,&chess_board,entity,&mut piece,&mut transform,&mut name,children,PieceKind::Pawn,mv.from);
+                    }
+                }
+                else if let (Some(sq),Some((rook_to,rook_from))) = (pos,taken_back.rook){
+                    if sq == rook_to{
+                        transform.translation = square_translation(rook_from);
+                        piece.move_count-=1;
+                    }
+                }
+            }
+            if let Some((piece,sq)) = taken_back.captured{
+                let colliders = collider_materials(&mut materials);
+                spawn_piece(&mut commands,&mut meshes,&chess_board,&colliders,piece,sq);
+            }
+            taken_back.before
+        }
+        //moves that came with a loaded game are not in the history,
+        //the pieces are set up again instead
+        None => {
+            let before = gs.record.position_at(ply);
+            for (entity,_,_,_,_) in query.iter(){
+                commands.entity(entity).despawn_recursive();
+            }
+            spawn_pieces(&mut commands,&mut materials,&mut meshes,&chess_board,&before);
+            before
+        }
+    };
+
+    b_db.position = before;
+    //game_status_script adds the restored position again
+    gs.position_history.truncate(ply);
+    gs.result = None;
+    gs.record.set_result("*");
+    gs.claimable_draw = None;
+    gs.status_pending = true;
+    clock.rewind(&gs.record);
+    //a selection made before the undo points at the old position
+    for entity in squares.iter(){
+        commands.entity(entity).despawn_recursive();
+    }
+    *db = MovementDataBase{data:HashMap::new(),piece_id:"".to_string()};
+    *ct = Controller::new();
+}
+
+//turns a piece entity into another kind: tag, name, collider and model
+fn set_piece_kind(
+    commands:&mut Commands,
+    chess_board:&ChessBoard,
+    entity:Entity,
+    piece:&mut Piece,
+    transform:&mut Transform,
+    name:&mut Name,
+    children:&Children,
+    kind:PieceKind,
+    sq:board::Square){
+
+    piece.tag = kind.name().to_string();
+    *name = Name::new(piece_name(piece));
+    transform.scale.y = collider_height(&piece.tag);
+    for child in children.iter(){
+        commands.entity(*child).despawn_recursive();
+    }
+    let model = piece_model(chess_board,piece,sq);
+    let model_name = format!("{} model",piece_name(piece));
+    commands.entity(entity).with_children(|commands| {
+        commands.spawn(model)
+        .insert(Name::new(model_name));
+    });
+}
+
+//checkmate/stalemate detection for the side that just got the move
+fn game_status_script(
+    mut gs:ResMut<GameState>,
+    b_db:Res<BoardDataBase>,
+    ){
+    if !gs.status_pending{
+        return
+    }
+    gs.status_pending = false;
+    let position = b_db.position;
+    let side = position.side_to_move();
+    gs.position_history.push(position);
+    let repetitions = gs.position_history.iter().filter(|i| i.same_position(&position)).count();
+    gs.claimable_draw = None;
+
+    let draw = |reason:&str| GameResult{winner:None,reason:reason.to_string()};
+    if !position.has_legal_move(){
+        if position.is_check(){
+            gs.finish(GameResult{winner:Some(side.opposite()),reason:"checkmate".to_string()});
+        }
+        else{
+            gs.finish(draw("stalemate"));
+        }
+    }
+    //automatic draws
+    else if position.is_insufficient_material(){
+        gs.finish(draw("insufficient material"));
+    }
+    else if repetitions >= 5{
+        gs.finish(draw("fivefold repetition"));
+    }
+    else if position.halfmove_clock() >= 150{
+        gs.finish(draw("the seventy-five-move rule"));
+    }
+    //draws the player has to ask for
+    else if repetitions >= 3{
+        gs.claimable_draw = Some("threefold repetition".to_string());
+    }
+    else if position.halfmove_clock() >= 100{
+        gs.claimable_draw = Some("the fifty-move rule".to_string());
+    }
+}
+
+//offers the side to move a draw it is entitled to claim
+fn draw_claim_panel(
+    mut egui_context:ResMut<EguiContext>,
+    mut gs:ResMut<GameState>,
+    b_db:Res<BoardDataBase>,
+    ){
+    if gs.result.is_some() || gs.promotion.is_some(){
+        return
+    }
+    let reason = match &gs.claimable_draw{
+        Some(reason) => reason.clone(),
+        None => return,
+    };
+    let mut claimed = false;
+    egui::Window::new("Draw")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::RIGHT_TOP, [-10., 10.])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!("{:?} may claim a draw by {}",b_db.position.side_to_move(),reason));
+            if ui.button("Claim draw").clicked(){
+                claimed = true;
+            }
+        });
+    if claimed{
+        gs.finish(GameResult{winner:None,reason});
+        gs.claimable_draw = None;
+    }
+}
+
+//runs the clock of the side to move; a flag fall loses the game unless the
+//opponent has nothing left to mate with
+fn clock_script(
+    time:Res<Time>,
+    mut clock:ResMut<GameClock>,
+    mut gs:ResMut<GameState>,
+    b_db:Res<BoardDataBase>,
+    ){
+    let clock = match &mut clock.clock{
+        Some(clock) => clock,
+        None => return,
+    };
+    if gs.result.is_some(){
+        clock.stop();
+        return
+    }
+    clock.tick(time.delta());
+    if let Some(flagged) = clock.flagged(){
+        clock.stop();
+        let opponent = flagged.opposite();
+        if b_db.position.has_mating_material(opponent){
+            gs.finish(GameResult{winner:Some(opponent),reason:"time forfeit".to_string()});
+        }
+        else{
+            gs.finish(GameResult{winner:None,reason:"timeout against insufficient material".to_string()});
+        }
+    }
+}
+
+//both clocks, and the time control the next game is played with
+fn clock_panel(
+    mut egui_context:ResMut<EguiContext>,
+    mut clock:ResMut<GameClock>,
+    mut load:EventWriter<LoadGame>,
+    ){
+    egui::Window::new("Clock")
+        .anchor(egui::Align2::CENTER_TOP, [0., 10.])
+        .show(egui_context.ctx_mut(), |ui| {
+            match &clock.clock{
+                Some(running) => {
+                    ui.label(running.control().to_string());
+                    for color in [PieceColor::White,PieceColor::Black]{
+                        let mut text = format!("{:?}  {}",color,clock_text(running.remaining(color)));
+                        if running.running() == Some(color) && !running.delay_left().is_zero(){
+                            text.push_str(&format!("  (delay {:.1})",running.delay_left().as_secs_f32()));
+                        }
+                        let text = egui::RichText::new(text).monospace().size(18.);
+                        if running.running() == Some(color){
+                            ui.label(text.strong());
+                        }
+                        else{
+                            ui.label(text.weak());
+                        }
+                    }
+                }
+                None => {
+                    ui.label("untimed game");
+                }
+            }
+
+            ui.collapsing("time control", |ui| {
+                ui.checkbox(&mut clock.timed,"play on the clock");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut clock.minutes).clamp_range(1..=180).suffix(" min"));
+                    ui.label("+");
+                    ui.add(egui::DragValue::new(&mut clock.bonus).clamp_range(0..=60).suffix(" s"));
+                });
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut clock.kind,Bonus::Fischer,"increment");
+                    ui.radio_value(&mut clock.kind,Bonus::Bronstein,"Bronstein");
+                    ui.radio_value(&mut clock.kind,Bonus::Delay,"delay");
+                });
+                ui.horizontal(|ui| {
+                    for (minutes,bonus) in TIME_CONTROLS{
+                        if ui.button(format!("{}+{}",minutes,bonus)).clicked(){
+                            clock.timed = true;
+                            clock.minutes = minutes;
+                            clock.bonus = bonus;
+                            clock.kind = Bonus::Fischer;
+                        }
+                    }
+                });
+                if ui.button("New game").clicked(){
+                    load.send(LoadGame(PgnGame::new(Position::startpos())));
+                }
+            });
+        });
+}
+
+//m:ss, with tenths once time gets short
+fn clock_text(remaining:Duration)->String{
+    let seconds = remaining.as_secs();
+    if seconds >= 3600{
+        pgn::format_clock(remaining)
+    }
+    else if seconds < 20{
+        format!("0:{:04.1}",remaining.as_secs_f32())
+    }
+    else{
+        format!("{}:{:02}",seconds/60,seconds%60)
+    }
+}
+
+//result window with a button to start over from the initial position
+fn game_over_overlay(
+    mut egui_context:ResMut<EguiContext>,
+    gs:Res<GameState>,
+    mut load:EventWriter<LoadGame>,
+    ){
+    let result = match &gs.result{
+        Some(result) => result.clone(),
+        None => return,
+    };
+    egui::Window::new("Game over")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0., 0.])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.heading(result.describe());
+            if ui.button("New game").clicked(){
+                load.send(LoadGame(PgnGame::new(Position::startpos())));
+            }
+        });
+}
+
+//shows the current position as FEN and sets up a pasted one
+fn fen_panel(
+    mut egui_context:ResMut<EguiContext>,
+    mut panel:ResMut<FenPanel>,
+    b_db:Res<BoardDataBase>,
+    mut load:EventWriter<LoadGame>,
+    ){
+    let fen = b_db.position.to_fen();
+    egui::Window::new("FEN")
+        .anchor(egui::Align2::LEFT_BOTTOM, [10., -10.])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label(fen.as_str());
+                if ui.button("Copy").clicked(){
+                    ui.output().copied_text = fen.clone();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut panel.input);
+                if ui.button("Load").clicked(){
+                    match Position::from_fen(&panel.input){
+                        Ok(position) => {
+                            panel.error = None;
+                            load.send(LoadGame(PgnGame::new(position)));
+                        }
+                        Err(error) => panel.error = Some(error),
+                    }
+                }
+            });
+            if let Some(error) = &panel.error{
+                ui.colored_label(egui::Color32::RED,format!("invalid FEN: {}",error));
+            }
+        });
+}
+
+//writes the game so far (finished or not) to a PGN file, and opens PGN files
+//to step through their games with the buttons or the arrow keys
+fn pgn_panel(
+    mut egui_context:ResMut<EguiContext>,
+    mut panel:ResMut<PgnPanel>,
+    gs:Res<GameState>,
+    keyboard:Res<Input<KeyCode>>,
+    mut load:EventWriter<LoadGame>,
+    mut play:EventWriter<PlayMove>,
+    ){
+    //replay navigation: -1 one move back, 1 one forward, i32::MIN/MAX to either end
+    let mut step = 0;
+    let mut open = None;
+    let typing = egui_context.ctx_mut().wants_keyboard_input();
+    egui::Window::new("PGN")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10., -10.])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut panel.path);
+                if ui.button("Save").clicked(){
+                    panel.status = Some(match std::fs::write(&panel.path,gs.record.to_pgn()){
+                        Ok(_) => format!("saved {} moves to {}",gs.record.moves.len(),panel.path),
+                        Err(error) => format!("could not save: {}",error),
+                    });
+                }
+                if ui.button("Open").clicked(){
+                    let read = std::fs::read_to_string(&panel.path).map_err(|e| e.to_string());
+                    match read.and_then(|text| pgn::parse_pgn(&text)){
+                        Ok(games) => {
+                            panel.status = Some(format!("{} games in {}",games.len(),panel.path));
+                            panel.games = games;
+                        }
+                        Err(error) => panel.status = Some(format!("could not open: {}",error)),
+                    }
+                }
+            });
+            if let Some(status) = &panel.status{
+                ui.label(status.as_str());
             }
+
+            egui::ScrollArea::vertical().max_height(150.).show(ui, |ui| {
+                for (i,game) in panel.games.iter().enumerate(){
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}. {} - {} {}",i+1,
+                            game.tag("White").unwrap_or("?"),game.tag("Black").unwrap_or("?"),game.result()));
+                        if ui.button("Replay").clicked(){
+                            open = Some(i);
+                        }
+                    });
+                }
+            });
+
+            if let Some(replay) = &panel.replay{
+                ui.separator();
+                ui.label(format!("move {} of {}",replay.ply,replay.game.moves.len()));
+                ui.horizontal(|ui| {
+                    if ui.button("|<").clicked(){
+                        step = i32::MIN;
+                    }
+                    if ui.button("<").clicked(){
+                        step = -1;
+                    }
+                    if ui.button(">").clicked(){
+                        step = 1;
+                    }
+                    if ui.button(">|").clicked(){
+                        step = i32::MAX;
+                    }
+                });
+            }
+        });
+
+    if let Some(i) = open{
+        let game = panel.games[i].clone();
+        load.send(LoadGame(game.truncated(0)));
+        panel.replay = Some(Replay{game,ply:0});
+        return
+    }
+    if !typing{
+        if keyboard.just_pressed(KeyCode::Left){
+            step = -1;
+        }
+        if keyboard.just_pressed(KeyCode::Right){
+            step = 1;
+        }
+    }
+    let replay = match &mut panel.replay{
+        Some(replay) if step != 0 => replay,
+        _ => return,
+    };
+    let len = replay.game.moves.len();
+    //moves made on the board since leave the replay behind, stepping forward
+    //then starts over from the replayed position
+    let in_step = gs.record.moves.len() == replay.ply
+        && gs.record.moves.iter().zip(replay.game.moves.iter()).all(|(a,b)| a.mv == b.mv);
+    match step{
+        1 if replay.ply < len && in_step => {
+            play.send(PlayMove(replay.game.moves[replay.ply].mv));
+            replay.ply+=1;
+        }
+        1 if replay.ply < len => {
+            load.send(LoadGame(replay.game.truncated(replay.ply)));
+        }
+        -1 if replay.ply > 0 => {
+            replay.ply-=1;
+            load.send(LoadGame(replay.game.truncated(replay.ply)));
         }
+        i32::MIN => {
+            replay.ply = 0;
+            load.send(LoadGame(replay.game.truncated(0)));
+        }
+        i32::MAX => {
+            replay.ply = len;
+            load.send(LoadGame(replay.game.clone()));
+        }
+        _ => {}
+    }
+}
+
+//replaces the game with the given record, its moves already played
+fn load_game_script(
+    mut commands:Commands,
+    mut events:EventReader<LoadGame>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    chess_board:Res<ChessBoard>,
+    mut gs:ResMut<GameState>,
+    mut b_db:ResMut<BoardDataBase>,
+    mut db:ResMut<MovementDataBase>,
+    mut ct:ResMut<Controller>,
+    mut clock:ResMut<GameClock>,
+    mut ai:ResMut<AiPlayer>,
+    query:Query<Entity,Or<(With<Piece>,With<Square>)>>,
+    ){
+    let mut record = match events.iter().last(){
+        Some(LoadGame(record)) => record.clone(),
+        None => return,
+    };
+    let tag = clock.control().and_then(|control| control.pgn_tag());
+    if let (Some(tag),true) = (tag,record.tag("TimeControl").is_none()){
+        record.set_tag("TimeControl",&tag);
+    }
+    clock.start_game(&record);
+    ai.new_game = true;
+    for entity in query.iter(){
+        commands.entity(entity).despawn_recursive();
     }
+    let position = record.position_at(record.moves.len());
+    *gs = GameState::from_record(record);
+    *b_db = BoardDataBase{position};
+    *db = MovementDataBase{data:HashMap::new(),piece_id:"".to_string()};
+    *ct = Controller::new();
+    spawn_pieces(&mut commands,&mut materials,&mut meshes,&chess_board,&position);
 }
 
 fn purge_square_script(
@@ -1036,26 +1848,23 @@ fn purge_square_script(
 }
 
 
-fn update_board_state(
-    mut db:ResMut<BoardDataBase>,
-    mut selection:Query<(&Transform,&Name),With<Piece>>
-    ){
-    let mut data = HashMap::new();
-    for (transform,name) in &selection{
-        let _x = transform.translation[0];
-        let _z = transform.translation[2];
-        let pos = chess_pos(_x,_z);
-        data.insert(pos,name.to_string());
-    }
-    db.data = data;
+//the only place that knows the board geometry: a1 sits at x=21, z=-21 and
+//h8 at x=-21, z=21, one square is SQUARE_SIZE wide
+fn square_translation(sq:board::Square)->Vec3{
+    Vec3::new(
+        BOARD_EDGE-sq.file() as f32*SQUARE_SIZE,
+        0.,
+        sq.rank() as f32*SQUARE_SIZE-BOARD_EDGE)
 }
 
-fn chess_pos(x:f32,z:f32)->String{
-    //normalizing negative values
-    let x = -x;
-    let file = (((x+21.)/6.).round()+97.) as u8 as char;
-    let rank = (((z+21.)/6.).round()+1.);
-    return format!("{}{}",file,rank)
+//square under a world position, None off the board
+fn translation_square(translation:Vec3)->Option<board::Square>{
+    let file = ((BOARD_EDGE-translation.x)/SQUARE_SIZE).round();
+    let rank = ((translation.z+BOARD_EDGE)/SQUARE_SIZE).round();
+    if (0. ..8.).contains(&file) && (0. ..8.).contains(&rank){
+        Some(board::Square::new(file as u8,rank as u8))
+    }
+    else{
+        None
+    }
 }
-
-
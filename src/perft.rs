@@ -0,0 +1,37 @@
+use crate::board::{Move, Position};
+
+//move path enumeration: counts the leaf nodes of the legal move tree to a
+//fixed depth, the standard way to check a move generator against known numbers
+
+pub fn perft(position:&Position,depth:u32)->u64{
+    if depth == 0{
+        return 1
+    }
+    let moves = position.legal_moves();
+    //the moves at the last ply need not be played to be counted
+    if depth == 1{
+        return moves.len() as u64
+    }
+    moves.iter()
+        .map(|mv| {
+            let mut next = *position;
+            next.play(mv);
+            perft(&next,depth-1)
+        })
+        .sum()
+}
+
+//perft split by the first move, to narrow down where two generators disagree
+pub fn divide(position:&Position,depth:u32)->Vec<(Move,u64)>{
+    if depth == 0{
+        return Vec::new()
+    }
+    position.legal_moves()
+        .into_iter()
+        .map(|mv| {
+            let mut next = *position;
+            next.play(&mv);
+            (mv,perft(&next,depth-1))
+        })
+        .collect()
+}
@@ -0,0 +1,392 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::board::{Color, Move, Position};
+
+//portable game notation: tag pairs followed by SAN move text
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct PgnMove{
+    pub mv:Move,
+    //time left on the mover's clock after the move, written as {[%clk h:mm:ss]}
+    pub clock:Option<Duration>,
+    pub comment:Option<String>,
+    //numeric annotation glyphs, "!" is $1, "?" is $2 and so on
+    pub nags:Vec<u8>,
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct PgnGame{
+    //in output order, the Seven Tag Roster comes first
+    pub tags:Vec<(String,String)>,
+    pub start:Position,
+    pub moves:Vec<PgnMove>,
+}
+
+pub const SEVEN_TAG_ROSTER:[&str;7] = ["Event","Site","Date","Round","White","Black","Result"];
+
+impl PgnGame{
+    //an unfinished game from `start` with the Seven Tag Roster filled with defaults
+    pub fn new(start:Position)->PgnGame{
+        let mut game = PgnGame{tags:Vec::new(),start,moves:Vec::new()};
+        game.set_tag("Event","Casual game");
+        game.set_tag("Site","?");
+        game.set_tag("Date",&date_today());
+        game.set_tag("Round","-");
+        game.set_tag("White","?");
+        game.set_tag("Black","?");
+        game.set_tag("Result","*");
+        if start != Position::startpos(){
+            game.set_tag("SetUp","1");
+            game.set_tag("FEN",&start.to_fen());
+        }
+        game
+    }
+
+    pub fn tag(&self,name:&str)->Option<&str>{
+        self.tags.iter().find(|(key,_)| key == name).map(|(_,value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self,name:&str,value:&str){
+        match self.tags.iter_mut().find(|(key,_)| key == name){
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(),value.to_string())),
+        }
+    }
+
+    //"1-0", "0-1", "1/2-1/2" or "*" while the game goes on
+    pub fn result(&self)->&str{
+        self.tag("Result").unwrap_or("*")
+    }
+
+    pub fn set_result(&mut self,result:&str){
+        self.set_tag("Result",result);
+    }
+
+    pub fn push(&mut self,mv:Move,clock:Option<Duration>){
+        self.moves.push(PgnMove{mv,clock,comment:None,nags:Vec::new()});
+    }
+
+    //position after the first `ply` moves
+    pub fn position_at(&self,ply:usize)->Position{
+        let mut position = self.start;
+        for record in self.moves.iter().take(ply){
+            position.play(&record.mv);
+        }
+        position
+    }
+
+    //the same game cut off after `ply` moves, the result is open again unless nothing was cut
+    pub fn truncated(&self,ply:usize)->PgnGame{
+        let mut game = self.clone();
+        if ply < game.moves.len(){
+            game.moves.truncate(ply);
+            game.set_result("*");
+        }
+        game
+    }
+
+    pub fn to_pgn(&self)->String{
+        let mut pgn = String::new();
+        //roster tags first and in their fixed order, the rest as they were added
+        for name in SEVEN_TAG_ROSTER{
+            pgn.push_str(&format!("[{} \"{}\"]\n",name,escape(self.tag(name).unwrap_or("?"))));
+        }
+        for (name,value) in self.tags.iter(){
+            if !SEVEN_TAG_ROSTER.contains(&name.as_str()){
+                pgn.push_str(&format!("[{} \"{}\"]\n",name,escape(value)));
+            }
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        let mut position = self.start;
+        for (i,record) in self.moves.iter().enumerate(){
+            if position.side_to_move() == Color::White{
+                tokens.push(format!("{}.",position.fullmove_number()));
+            }
+            else if i == 0{
+                tokens.push(format!("{}...",position.fullmove_number()));
+            }
+            tokens.push(position.san(&record.mv));
+            for nag in record.nags.iter(){
+                tokens.push(format!("${}",nag));
+            }
+            if let Some(clock) = record.clock{
+                tokens.push(format!("{{[%clk {}]}}",format_clock(clock)));
+            }
+            if let Some(comment) = &record.comment{
+                tokens.push(format!("{{{}}}",comment.replace('}',")")));
+            }
+            position.play(&record.mv);
+        }
+        tokens.push(self.result().to_string());
+
+        //export format keeps lines under 80 characters
+        let mut line = String::new();
+        for token in tokens{
+            if !line.is_empty() && line.len()+1+token.len() > 79{
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty(){
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push_str("\n\n");
+        pgn
+    }
+}
+
+//reads every game in a PGN file; comments and NAGs are kept on the move they
+//follow, variations are skipped
+pub fn parse_pgn(text:&str)->Result<Vec<PgnGame>,String>{
+    let mut games = Vec::new();
+    let mut tokens = tokenize(text)?.into_iter().peekable();
+    while tokens.peek().is_some(){
+        let number = games.len()+1;
+        let mut tags = Vec::new();
+        while let Some(Token::Tag(name,value)) = tokens.peek(){
+            tags.push((name.clone(),value.clone()));
+            tokens.next();
+        }
+
+        let start = match tags.iter().find(|(name,_)| name == "FEN"){
+            Some((_,fen)) => Position::from_fen(fen).map_err(|e| format!("game {}: bad FEN tag: {}",number,e))?,
+            None => Position::startpos(),
+        };
+        let mut game = PgnGame{tags,start,moves:Vec::new()};
+        let mut position = start;
+        let mut depth = 0;
+        while let Some(token) = tokens.peek(){
+            //a tag after move text starts the next game
+            if depth == 0 && matches!(token,Token::Tag(..)){
+                break
+            }
+            let token = tokens.next().unwrap();
+            match token{
+                Token::Tag(..) => {}
+                Token::Open => depth+=1,
+                Token::Close => {
+                    if depth == 0{
+                        return Err(format!("game {}: unmatched ')'",number))
+                    }
+                    depth-=1;
+                }
+                _ if depth > 0 => {}
+                Token::Result(result) => {
+                    game.set_result(&result);
+                    break
+                }
+                Token::Comment(comment) => {
+                    let (clock,rest) = split_clock(&comment);
+                    //a comment before the first move has nowhere to go
+                    if let Some(record) = game.moves.last_mut(){
+                        if clock.is_some(){
+                            record.clock = clock;
+                        }
+                        if !rest.is_empty(){
+                            record.comment = Some(rest);
+                        }
+                    }
+                }
+                Token::Nag(nag) => {
+                    if let Some(record) = game.moves.last_mut(){
+                        record.nags.push(nag);
+                    }
+                }
+                Token::San(san) => {
+                    let mv = position.parse_san(&san)
+                        .map_err(|e| format!("game {}, move {}: {}",number,position.fullmove_number(),e))?;
+                    let nags = suffix_nag(&san).into_iter().collect();
+                    position.play(&mv);
+                    game.moves.push(PgnMove{mv,clock:None,comment:None,nags});
+                }
+            }
+        }
+        if game.tag("Result").is_none(){
+            game.set_result("*");
+        }
+        games.push(game);
+    }
+    Ok(games)
+}
+
+#[derive(Debug)]
+enum Token{
+    Tag(String,String),
+    San(String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+}
+
+fn tokenize(text:&str)->Result<Vec<Token>,String>{
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+    while let Some(c) = chars.next(){
+        match c{
+            '\n' => {
+                line_start = true;
+                continue
+            }
+            //escape lines are ignored
+            '%' if line_start => {
+                while chars.peek().is_some_and(|c| *c != '\n'){
+                    chars.next();
+                }
+            }
+            ';' => {
+                let mut comment = String::new();
+                while let Some(c) = chars.peek(){
+                    if *c == '\n'{
+                        break
+                    }
+                    comment.push(*c);
+                    chars.next();
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop{
+                    match chars.next(){
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err("unterminated comment".to_string()),
+                    }
+                }
+                tokens.push(Token::Comment(comment.split_whitespace().collect::<Vec<_>>().join(" ")));
+            }
+            '[' => {
+                //[Name "value"] with \" and \\ escaped inside the value
+                while chars.peek().is_some_and(|c| c.is_whitespace()){
+                    chars.next();
+                }
+                let mut name = String::new();
+                while chars.peek().is_some_and(|c| !c.is_whitespace() && *c != '"' && *c != ']'){
+                    name.push(chars.next().unwrap());
+                }
+                let mut value = String::new();
+                let mut quoted = false;
+                loop{
+                    match chars.next(){
+                        Some('\\') if quoted => {
+                            if let Some(c) = chars.next(){
+                                value.push(c);
+                            }
+                        }
+                        Some('"') => quoted = !quoted,
+                        Some(']') if !quoted => break,
+                        Some(c) if quoted => value.push(c),
+                        Some(_) => {}
+                        None => return Err(format!("unterminated tag '{}'",name)),
+                    }
+                }
+                tokens.push(Token::Tag(name,value));
+            }
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '$' => {
+                let mut digits = String::new();
+                while chars.peek().is_some_and(|c| c.is_ascii_digit()){
+                    digits.push(chars.next().unwrap());
+                }
+                let nag = digits.parse().map_err(|_| format!("bad NAG '${}'",digits))?;
+                tokens.push(Token::Nag(nag));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = c.to_string();
+                while chars.peek().is_some_and(|c| !c.is_whitespace() && !"{}()[];$".contains(*c)){
+                    word.push(chars.next().unwrap());
+                }
+                match word.as_str(){
+                    "1-0" | "0-1" | "1/2-1/2" | "*" => tokens.push(Token::Result(word)),
+                    _ => {
+                        //move numbers may stick to the move: "12.Nf3", "12...Nf3"
+                        let san = match word.find('.'){
+                            Some(dot) if word[..dot].chars().all(|c| c.is_ascii_digit()) => word[dot..].trim_start_matches('.'),
+                            _ => word.as_str(),
+                        };
+                        if !san.is_empty(){
+                            tokens.push(Token::San(san.to_string()));
+                        }
+                    }
+                }
+            }
+        }
+        line_start = false;
+    }
+    Ok(tokens)
+}
+
+//"!" and "?" written straight after the move
+fn suffix_nag(san:&str)->Option<u8>{
+    let suffix = san.trim_start_matches(|c| c != '!' && c != '?');
+    match suffix{
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+//pulls a [%clk h:mm:ss] command out of a comment
+fn split_clock(comment:&str)->(Option<Duration>,String){
+    let start = match comment.find("[%clk"){
+        Some(start) => start,
+        None => return (None,comment.to_string()),
+    };
+    let end = match comment[start..].find(']'){
+        Some(end) => start+end,
+        None => return (None,comment.to_string()),
+    };
+    //readings that are not a time (negative, "inf", garbage) stay in the comment
+    let mut seconds = 0.0;
+    for part in comment[start+5..end].trim().split(':'){
+        match part.parse::<f64>(){
+            Ok(value) if value.is_finite() && value >= 0.0 => seconds = seconds*60.0+value,
+            _ => return (None,comment.to_string()),
+        }
+    }
+    let clock = match Duration::try_from_secs_f64(seconds){
+        Ok(clock) => clock,
+        Err(_) => return (None,comment.to_string()),
+    };
+    let rest = format!("{} {}",&comment[..start],&comment[end+1..]);
+    (Some(clock),rest.trim().to_string())
+}
+
+fn escape(value:&str)->String{
+    value.replace('\\',"\\\\").replace('"',"\\\"")
+}
+
+//h:mm:ss as the %clk command wants it
+pub fn format_clock(clock:Duration)->String{
+    let seconds = clock.as_secs();
+    format!("{}:{:02}:{:02}",seconds/3600,seconds/60%60,seconds%60)
+}
+
+//"YYYY.MM.DD" in UTC
+pub fn date_today()->String{
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    //days since 1970-01-01 to a civil date (Howard Hinnant's algorithm)
+    let days = (seconds/86400) as i64+719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era-day_of_era/1460+day_of_era/36524-day_of_era/146096)/365;
+    let day_of_year = day_of_era-(365*year_of_era+year_of_era/4-year_of_era/100);
+    let mp = (5*day_of_year+2)/153;
+    let day = day_of_year-(153*mp+2)/5+1;
+    let month = if mp < 10 {mp+3} else {mp-9};
+    let year = year_of_era+era*400+if month <= 2 {1} else {0};
+    format!("{:04}.{:02}.{:02}",year,month,day)
+}
@@ -0,0 +1,131 @@
+use crate::board::{Move, PieceKind, Position, Square};
+
+//standard algebraic notation as used in PGN: "e4", "Nbd7", "exd6", "O-O", "e8=Q#"
+
+impl Position{
+    //mv has to be legal in this position
+    pub fn san(&self,mv:&Move)->String{
+        let mut san = String::new();
+        let piece = match self.piece_at(mv.from){
+            Some(piece) => piece,
+            None => return mv.to_string(),
+        };
+
+        if let Some((rook_from,_)) = self.castling_rook(mv){
+            san.push_str(if rook_from.file() == 7 {"O-O"} else {"O-O-O"});
+        }
+        else if piece.kind == PieceKind::Pawn{
+            if self.is_capture(mv){
+                san.push((b'a'+mv.from.file()) as char);
+                san.push('x');
+            }
+            san.push_str(&mv.to.name());
+            if let Some(kind) = mv.promotion{
+                san.push('=');
+                san.push(kind.letter().to_ascii_uppercase());
+            }
+        }
+        else{
+            san.push(piece.kind.letter().to_ascii_uppercase());
+            //other pieces of the same kind that could go to the same square
+            let rivals:Vec<Move> = self.legal_moves()
+                .into_iter()
+                .filter(|other| other.to == mv.to && other.from != mv.from
+                    && self.piece_at(other.from) == Some(piece))
+                .collect();
+            if !rivals.is_empty(){
+                let file_clash = rivals.iter().any(|other| other.from.file() == mv.from.file());
+                let rank_clash = rivals.iter().any(|other| other.from.rank() == mv.from.rank());
+                if !file_clash{
+                    san.push((b'a'+mv.from.file()) as char);
+                }
+                else if !rank_clash{
+                    san.push((b'1'+mv.from.rank()) as char);
+                }
+                else{
+                    san.push_str(&mv.from.name());
+                }
+            }
+            if self.is_capture(mv){
+                san.push('x');
+            }
+            san.push_str(&mv.to.name());
+        }
+
+        let mut after = *self;
+        after.play(mv);
+        if after.is_check(){
+            san.push(if after.has_legal_move() {'+'} else {'#'});
+        }
+        san
+    }
+}
+
+impl Position{
+    //reads one SAN move and finds the legal move it stands for; accepts the
+    //usual sloppiness: check/annotation suffixes, "0-0", "e8Q" without '='
+    pub fn parse_san(&self,san:&str)->Result<Move,String>{
+        let text = san.trim_end_matches(['+','#','!','?']);
+        let legal = self.legal_moves();
+
+        if text == "O-O" || text == "0-0" || text == "O-O-O" || text == "0-0-0"{
+            let king_side = text.len() == 3;
+            return legal.into_iter()
+                .find(|mv| match self.castling_rook(mv){
+                    Some((rook_from,_)) => (rook_from.file() == 7) == king_side,
+                    None => false,
+                })
+                .ok_or(format!("illegal castling '{}'",san))
+        }
+
+        let mut chars:Vec<char> = text.chars().collect();
+        let kind = match chars.first(){
+            Some(c) if c.is_ascii_uppercase() => {
+                let kind = PieceKind::from_letter(*c).ok_or(format!("unknown piece in '{}'",san))?;
+                chars.remove(0);
+                kind
+            }
+            _ => PieceKind::Pawn,
+        };
+
+        let mut promotion = None;
+        if let Some(last) = chars.last(){
+            if last.is_ascii_alphabetic() && "QRBNqrbn".contains(*last) && kind == PieceKind::Pawn{
+                promotion = PieceKind::from_letter(*last);
+                chars.pop();
+                if chars.last() == Some(&'='){
+                    chars.pop();
+                }
+            }
+        }
+
+        //what is left: [from file][from rank][x]to square
+        chars.retain(|c| *c != 'x' && *c != '-' && *c != ':');
+        if chars.len() < 2{
+            return Err(format!("no target square in '{}'",san))
+        }
+        let target:String = chars[chars.len()-2..].iter().collect();
+        let to = Square::from_name(&target).ok_or(format!("bad target square in '{}'",san))?;
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in chars[..chars.len()-2].iter(){
+            match c{
+                'a'..='h' => from_file = Some(*c as u8-b'a'),
+                '1'..='8' => from_rank = Some(*c as u8-b'1'),
+                _ => return Err(format!("unexpected '{}' in '{}'",c,san)),
+            }
+        }
+
+        let candidates:Vec<Move> = legal.into_iter()
+            .filter(|mv| mv.to == to && mv.promotion == promotion)
+            .filter(|mv| self.piece_at(mv.from).map(|p| p.kind) == Some(kind))
+            .filter(|mv| from_file.is_none_or(|file| mv.from.file() == file))
+            .filter(|mv| from_rank.is_none_or(|rank| mv.from.rank() == rank))
+            .collect();
+        match candidates.len(){
+            1 => Ok(candidates[0]),
+            0 => Err(format!("illegal move '{}'",san)),
+            _ => Err(format!("ambiguous move '{}'",san)),
+        }
+    }
+}
@@ -0,0 +1,457 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use crate::board::{Color, Move, PieceKind, Position};
+use crate::syzygy::{Tablebase, Wdl};
+
+//computer player: iterative deepening negamax with alpha-beta pruning and a
+//quiescence search over captures; moves are tried best guess first (previous
+//best line, captures by most valuable victim / least valuable attacker,
+//killer moves); with endgame tablebases the root moves are cut down to the
+//ones that keep the tablebase result, and positions the tables cover after a
+//capture or pawn move are not searched any further
+
+//scores are in centipawns from the side to move's point of view,
+//a mate in n plies scores MATE-n
+pub const MATE:i32 = 30000;
+const INFINITY:i32 = 32000;
+const MAX_PLY:usize = 64;
+//a win the tablebases know of, sooner is better; below any mate
+const TABLEBASE_WIN:i32 = MATE-2*MAX_PLY as i32;
+
+//how far and how long to search; the search stops at whichever comes first
+#[derive(Clone,Copy,Debug)]
+pub struct Limits{
+    pub depth:u32,
+    pub movetime:Option<Duration>,
+    pub nodes:Option<u64>,
+}
+
+impl Limits{
+    pub fn depth(depth:u32)->Limits{
+        Limits{depth,movetime:None,nodes:None}
+    }
+
+    pub fn movetime(movetime:Duration)->Limits{
+        Limits{depth:MAX_PLY as u32,movetime:Some(movetime),nodes:None}
+    }
+}
+
+//strength levels of the computer: name, search depth and time per move in milliseconds
+pub const LEVELS:[(&str,u32,u64);4] = [
+    ("beginner",1,100),
+    ("casual",2,300),
+    ("club",4,1000),
+    ("strong",64,3000),
+];
+
+//share of the clock to spend on one move: an even split over the moves left
+//(30 if nobody says), most of the increment, and a margin so the flag never falls
+pub fn time_for_move(remaining:Duration,increment:Duration,moves_to_go:Option<u32>)->Duration{
+    let moves_to_go = moves_to_go.unwrap_or(30).max(1);
+    let budget = remaining/moves_to_go+increment*3/4;
+    let margin = Duration::from_millis(50);
+    budget.min(remaining.saturating_sub(margin)).max(Duration::from_millis(1))
+}
+
+//outcome of the deepest finished iteration
+#[derive(Clone,Debug)]
+pub struct SearchInfo{
+    pub depth:u32,
+    pub score:i32,
+    //principal variation, best move first; empty without legal moves
+    pub pv:Vec<Move>,
+    pub nodes:u64,
+    //positions the tablebases answered
+    pub tbhits:u64,
+    pub time:Duration,
+}
+
+impl SearchInfo{
+    pub fn best_move(&self)->Option<Move>{
+        self.pv.first().copied()
+    }
+
+    //Some(n) when the score is a forced mate in n moves, negative when getting mated
+    pub fn mate_in(&self)->Option<i32>{
+        if self.score.abs() < MATE-MAX_PLY as i32{
+            return None
+        }
+        let plies = MATE-self.score.abs();
+        let moves = (plies+1)/2;
+        Some(if self.score > 0 {moves} else {-moves})
+    }
+}
+
+pub fn search(position:&Position,limits:Limits,stop:&AtomicBool)->SearchInfo{
+    search_with(position,limits,stop,None,|_| {})
+}
+
+//like search, with endgame tablebases if there are any; `report` is called
+//after every finished iteration
+pub fn search_with(
+    position:&Position,
+    limits:Limits,
+    stop:&AtomicBool,
+    tablebase:Option<&Tablebase>,
+    mut report:impl FnMut(&SearchInfo))->SearchInfo{
+
+    let root_moves = tablebase.and_then(|tablebase| tablebase.best_moves(position));
+    let mut searcher = Searcher{
+        stop,
+        limits,
+        start:Instant::now(),
+        nodes:0,
+        stopped:false,
+        pv:vec![Vec::new();MAX_PLY+1],
+        killers:[[None;2];MAX_PLY+1],
+        tablebase,
+        tbhits:if root_moves.is_some() {1} else {0},
+        root_moves,
+    };
+    //something to play even if the first iteration gets cut off
+    let first = match &searcher.root_moves{
+        Some(moves) => moves.first().copied(),
+        None => position.legal_moves().first().copied(),
+    };
+    let mut info = SearchInfo{
+        depth:0,
+        score:0,
+        pv:first.into_iter().collect(),
+        nodes:0,
+        tbhits:searcher.tbhits,
+        time:Duration::ZERO,
+    };
+    //mated or stalemated, nothing to search
+    if info.pv.is_empty(){
+        info.score = if position.is_check() {-MATE} else {0};
+        return info
+    }
+    let max_depth = limits.depth.clamp(1,MAX_PLY as u32);
+    for depth in 1..=max_depth{
+        let score = searcher.negamax(position,depth,0,-INFINITY,INFINITY,&info.pv.clone());
+        if searcher.stopped{
+            break
+        }
+        info = SearchInfo{
+            depth,
+            score,
+            pv:searcher.pv[0].clone(),
+            nodes:searcher.nodes,
+            tbhits:searcher.tbhits,
+            time:searcher.start.elapsed(),
+        };
+        report(&info);
+        //no point looking deeper once a mate is found
+        if score.abs() >= MATE-depth as i32{
+            break
+        }
+    }
+    info.nodes = searcher.nodes;
+    info.tbhits = searcher.tbhits;
+    info.time = searcher.start.elapsed();
+    info
+}
+
+struct Searcher<'a>{
+    stop:&'a AtomicBool,
+    limits:Limits,
+    start:Instant,
+    nodes:u64,
+    stopped:bool,
+    //triangular table: pv[ply] is the best line found from that ply on
+    pv:Vec<Vec<Move>>,
+    //quiet moves that caused a cutoff at that ply in a sibling node
+    killers:[[Option<Move>;2];MAX_PLY+1],
+    tablebase:Option<&'a Tablebase>,
+    //the moves searched at the root when the tablebases cover it
+    root_moves:Option<Vec<Move>>,
+    tbhits:u64,
+}
+
+impl<'a> Searcher<'a>{
+    //checked every 1024 nodes, the clock is too slow to read on each one
+    fn should_stop(&mut self)->bool{
+        if self.stopped{
+            return true
+        }
+        if self.nodes.is_multiple_of(1024){
+            let out_of_time = self.limits.movetime.is_some_and(|t| self.start.elapsed() >= t);
+            let out_of_nodes = self.limits.nodes.is_some_and(|n| self.nodes >= n);
+            if out_of_time || out_of_nodes || self.stop.load(Ordering::Relaxed){
+                self.stopped = true;
+            }
+        }
+        self.stopped
+    }
+
+    fn negamax(&mut self,position:&Position,depth:u32,ply:usize,mut alpha:i32,beta:i32,previous_pv:&[Move])->i32{
+        self.pv[ply].clear();
+        if self.should_stop(){
+            return 0
+        }
+        let in_check = position.is_check();
+        //look one ply further when in check, the position is not quiet
+        let depth = if in_check {depth+1} else {depth};
+        if depth == 0 || ply >= MAX_PLY{
+            return self.quiesce(position,ply,alpha,beta)
+        }
+        self.nodes+=1;
+
+        let mut moves = position.legal_moves();
+        if moves.is_empty(){
+            return if in_check {-MATE+ply as i32} else {0}
+        }
+        if ply > 0 && (position.halfmove_clock() >= 100 || position.is_insufficient_material()){
+            return 0
+        }
+        //right after a capture or pawn move the 50 move count starts over,
+        //so the tables' result holds as it is
+        if ply > 0 && position.halfmove_clock() == 0{
+            if let Some(wdl) = self.tablebase.and_then(|tablebase| tablebase.probe_wdl(position)){
+                self.tbhits+=1;
+                return tablebase_score(wdl,ply)
+            }
+        }
+        if ply == 0{
+            if let Some(root_moves) = &self.root_moves{
+                moves.retain(|mv| root_moves.contains(mv));
+            }
+        }
+        let pv_move = previous_pv.get(ply).copied();
+        self.order(position,&mut moves,pv_move,ply);
+
+        let mut best = -INFINITY;
+        for mv in moves{
+            let mut next = *position;
+            next.play(&mv);
+            //only the first move follows the previous best line
+            let following = if Some(mv) == pv_move {previous_pv} else {&[]};
+            let score = -self.negamax(&next,depth-1,ply+1,-beta,-alpha,following);
+            if self.stopped{
+                return 0
+            }
+            if score > best{
+                best = score;
+            }
+            if score > alpha{
+                alpha = score;
+                let mut line = vec![mv];
+                line.extend_from_slice(&self.pv[ply+1]);
+                self.pv[ply] = line;
+            }
+            if alpha >= beta{
+                if !position.is_capture(&mv) && mv.promotion.is_none() && self.killers[ply][0] != Some(mv){
+                    self.killers[ply][1] = self.killers[ply][0];
+                    self.killers[ply][0] = Some(mv);
+                }
+                break
+            }
+        }
+        best
+    }
+
+    //only captures and promotions until the position is quiet, so the
+    //evaluation never looks at a piece that is about to be taken
+    fn quiesce(&mut self,position:&Position,ply:usize,mut alpha:i32,beta:i32)->i32{
+        self.pv[ply].clear();
+        if self.should_stop(){
+            return 0
+        }
+        self.nodes+=1;
+        let in_check = position.is_check();
+        //standing pat: the side to move does not have to capture, unless in check
+        if !in_check{
+            let stand_pat = evaluate(position);
+            if stand_pat >= beta || ply >= MAX_PLY{
+                return stand_pat
+            }
+            if stand_pat > alpha{
+                alpha = stand_pat;
+            }
+        }
+        let mut moves = position.legal_moves();
+        if in_check && moves.is_empty(){
+            return -MATE+ply as i32
+        }
+        if !in_check{
+            moves.retain(|mv| position.is_capture(mv) || mv.promotion.is_some());
+        }
+        self.order(position,&mut moves,None,MAX_PLY);
+
+        for mv in moves{
+            let mut next = *position;
+            next.play(&mv);
+            let score = -self.quiesce(&next,(ply+1).min(MAX_PLY),-beta,-alpha);
+            if self.stopped{
+                return 0
+            }
+            if score >= beta{
+                return score
+            }
+            if score > alpha{
+                alpha = score;
+            }
+        }
+        alpha
+    }
+
+    fn order(&self,position:&Position,moves:&mut [Move],pv_move:Option<Move>,ply:usize){
+        let killers = self.killers[ply.min(MAX_PLY)];
+        moves.sort_by_cached_key(|mv| {
+            if Some(*mv) == pv_move{
+                return i32::MIN
+            }
+            let mut key = 0;
+            if let Some(sq) = position.captured_square(mv){
+                //most valuable victim, least valuable attacker
+                let victim = position.piece_at(sq).map_or(PAWN,|p| piece_value(p.kind));
+                let attacker = position.piece_at(mv.from).map_or(0,|p| piece_value(p.kind));
+                key-=100_000+victim*10-attacker/10;
+            }
+            else if killers.contains(&Some(*mv)){
+                key-=50_000;
+            }
+            if let Some(kind) = mv.promotion{
+                key-=piece_value(kind)*10;
+            }
+            key
+        });
+    }
+}
+
+fn tablebase_score(wdl:Wdl,ply:usize)->i32{
+    match wdl{
+        Wdl::Win => TABLEBASE_WIN-ply as i32,
+        Wdl::Loss => -TABLEBASE_WIN+ply as i32,
+        //drawn by the 50 move rule, but only if the other side knows how
+        Wdl::CursedWin => 1,
+        Wdl::BlessedLoss => -1,
+        Wdl::Draw => 0,
+    }
+}
+
+const PAWN:i32 = 100;
+
+pub fn piece_value(kind:PieceKind)->i32{
+    match kind{
+        PieceKind::Pawn => PAWN,
+        PieceKind::Knight => 320,
+        PieceKind::Bishop => 330,
+        PieceKind::Rook => 500,
+        PieceKind::Queen => 900,
+        PieceKind::King => 0,
+    }
+}
+
+//material and piece-square tables, from the side to move's point of view
+pub fn evaluate(position:&Position)->i32{
+    //the king leaves its shelter once the heavy pieces are gone
+    let officers:i32 = position.pieces()
+        .filter(|(_,piece)| piece.kind != PieceKind::Pawn && piece.kind != PieceKind::King)
+        .map(|(_,piece)| piece_value(piece.kind))
+        .sum();
+    let endgame = officers <= 2*(piece_value(PieceKind::Rook)+piece_value(PieceKind::Knight));
+
+    let mut score = 0;
+    for (sq,piece) in position.pieces(){
+        //tables are written from white's side with the 8th rank on top
+        let index = match piece.color{
+            Color::White => (7-sq.rank() as usize)*8+sq.file() as usize,
+            Color::Black => sq.rank() as usize*8+sq.file() as usize,
+        };
+        let table = match piece.kind{
+            PieceKind::Pawn => &PAWN_TABLE,
+            PieceKind::Knight => &KNIGHT_TABLE,
+            PieceKind::Bishop => &BISHOP_TABLE,
+            PieceKind::Rook => &ROOK_TABLE,
+            PieceKind::Queen => &QUEEN_TABLE,
+            PieceKind::King => if endgame {&KING_END_TABLE} else {&KING_TABLE},
+        };
+        let value = piece_value(piece.kind)+table[index];
+        if piece.color == position.side_to_move(){
+            score+=value;
+        }
+        else{
+            score-=value;
+        }
+    }
+    score
+}
+
+//Tomasz Michniewski's simplified evaluation tables
+const PAWN_TABLE:[i32;64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+const KNIGHT_TABLE:[i32;64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+const BISHOP_TABLE:[i32;64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+const ROOK_TABLE:[i32;64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+const QUEEN_TABLE:[i32;64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+const KING_TABLE:[i32;64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+const KING_END_TABLE:[i32;64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
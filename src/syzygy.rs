@@ -0,0 +1,1235 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use crate::board::{Color, Move, Piece, PieceKind, Position};
+
+//Syzygy endgame tablebases: win/draw/loss (.rtbw) and distance to zeroing
+//(.rtbz) files from a directory, probed the way the generator laid them out
+//(see the probing code of Stockfish and Fathom). A file is read whole into
+//memory the first time a position needs it.
+//
+//WDL tables know nothing of the 50 move rule's counter but tell apart wins
+//that cannot be forced in time (cursed) from real ones. DTZ is the number of
+//plies to the next capture or pawn move on the best line, over 100 for
+//cursed wins and blessed losses.
+
+const WDL_MAGIC:[u8;4] = [0x71,0xe8,0x23,0x5d];
+const DTZ_MAGIC:[u8;4] = [0xd7,0x66,0x0c,0xa5];
+//largest tables anybody has, 7 men
+const MAX_PIECES:usize = 7;
+
+//the result with best play, from the side to move's point of view
+#[derive(Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Hash,Debug)]
+pub enum Wdl{
+    Loss,
+    //lost, but the 50 move rule comes first
+    BlessedLoss,
+    Draw,
+    //won, but not before the 50 move rule
+    CursedWin,
+    Win,
+}
+
+impl Wdl{
+    //-2 for a loss ... 2 for a win, as the tables store it
+    fn from_value(value:i32)->Wdl{
+        match value{
+            i32::MIN..=-2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win,
+        }
+    }
+
+    fn signum(&self)->i32{
+        match self{
+            Wdl::Loss | Wdl::BlessedLoss => -1,
+            Wdl::Draw => 0,
+            Wdl::CursedWin | Wdl::Win => 1,
+        }
+    }
+
+    //the same result seen from the other side
+    pub fn opposite(&self)->Wdl{
+        match self{
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+
+    pub fn name(&self)->&'static str{
+        match self{
+            Wdl::Loss => "loss",
+            Wdl::BlessedLoss => "blessed loss",
+            Wdl::Draw => "draw",
+            Wdl::CursedWin => "cursed win",
+            Wdl::Win => "win",
+        }
+    }
+}
+
+//a legal move with what it leads to, from the mover's point of view
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct MoveProbe{
+    pub mv:Move,
+    pub wdl:Wdl,
+    //None without the DTZ table
+    pub dtz:Option<i32>,
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
+enum Kind{
+    Wdl,
+    Dtz,
+}
+
+impl Kind{
+    fn extension(&self)->&'static str{
+        match self{
+            Kind::Wdl => "rtbw",
+            Kind::Dtz => "rtbz",
+        }
+    }
+}
+
+//why a table gave no value
+enum Failure{
+    //not in the directory, or not readable
+    Missing,
+    //DTZ tables only store one side to move, this is the other one
+    OtherSide,
+}
+
+pub struct Tablebase{
+    directory:PathBuf,
+    //material of the tables found, "KRPvKR" for KRPvKR.rtbw
+    wdl:HashSet<String>,
+    dtz:HashSet<String>,
+    max_pieces:usize,
+    loaded:Mutex<Loaded>,
+}
+
+//tables read so far; None for files that turned out broken
+type Loaded = HashMap<(String,Kind),Option<Arc<Table>>>;
+
+impl Tablebase{
+    //finds the tables in the directory, reading them waits until they are needed
+    pub fn open(directory:&str)->Result<Tablebase,String>{
+        let entries = std::fs::read_dir(directory).map_err(|e| format!("{}: {}",directory,e))?;
+        let mut tablebase = Tablebase{
+            directory:PathBuf::from(directory),
+            wdl:HashSet::new(),
+            dtz:HashSet::new(),
+            max_pieces:0,
+            loaded:Mutex::new(HashMap::new()),
+        };
+        for entry in entries.flatten(){
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let (name,extension) = match file_name.rsplit_once('.'){
+                Some(split) => split,
+                None => continue,
+            };
+            if Material::parse(name).is_none(){
+                continue
+            }
+            match extension{
+                "rtbw" => {
+                    tablebase.max_pieces = tablebase.max_pieces.max(name.len()-1);
+                    tablebase.wdl.insert(name.to_string());
+                }
+                "rtbz" => {
+                    tablebase.dtz.insert(name.to_string());
+                }
+                _ => {}
+            }
+        }
+        if tablebase.wdl.is_empty(){
+            return Err(format!("{}: no Syzygy tables (.rtbw) in there",directory))
+        }
+        Ok(tablebase)
+    }
+
+    //number of WDL tables
+    pub fn len(&self)->usize{
+        self.wdl.len()
+    }
+
+    pub fn is_empty(&self)->bool{
+        self.wdl.is_empty()
+    }
+
+    //most pieces (kings included) any table has
+    pub fn max_pieces(&self)->usize{
+        self.max_pieces
+    }
+
+    pub fn has_dtz(&self)->bool{
+        !self.dtz.is_empty()
+    }
+
+    //few enough pieces and no castling rights, the tables have none
+    pub fn covers(&self,position:&Position)->bool{
+        let castling = position.castling();
+        let can_castle = [Color::White,Color::Black].iter()
+            .any(|color| castling.king_side(*color) || castling.queen_side(*color));
+        !can_castle && position.pieces().count() <= self.max_pieces
+    }
+
+    pub fn probe_wdl(&self,position:&Position)->Option<Wdl>{
+        if !self.covers(position){
+            return None
+        }
+        self.search_wdl(position,false).ok().map(|(wdl,_)| wdl)
+    }
+
+    //plies to the next capture or pawn move with best play, positive when the
+    //side to move wins and 0 in a draw; the halfmove clock is not looked at
+    pub fn probe_dtz(&self,position:&Position)->Option<i32>{
+        if !self.covers(position){
+            return None
+        }
+        self.dtz(position).ok()
+    }
+
+    //every legal move with its result, None outside the tables
+    pub fn probe_moves(&self,position:&Position)->Option<Vec<MoveProbe>>{
+        if !self.covers(position){
+            return None
+        }
+        let mut probes = Vec::new();
+        for mv in position.legal_moves(){
+            let zeroing = is_zeroing(position,&mv);
+            let mut next = *position;
+            next.play(&mv);
+            let wdl = self.search_wdl(&next,false).ok()?.0.opposite();
+            let dtz = if zeroing{
+                Some(dtz_before_zeroing(wdl))
+            }
+            else{
+                //one ply further from the zeroing move than the reply
+                self.dtz(&next).ok().map(|dtz| {
+                    let dtz = -dtz;
+                    if next.is_checkmate() {1} else {dtz+dtz.signum()}
+                })
+            };
+            probes.push(MoveProbe{mv,wdl,dtz});
+        }
+        Some(probes)
+    }
+
+    //the moves that keep the best result; when the DTZ tables are there, only
+    //the quickest way to the next zeroing move when winning and the slowest
+    //when losing, which is enough to win won positions under the 50 move rule
+    pub fn best_moves(&self,position:&Position)->Option<Vec<Move>>{
+        let probes = self.probe_moves(position)?;
+        let best = probes.iter().map(|probe| probe.wdl).max()?;
+        let mut moves:Vec<MoveProbe> = probes.into_iter().filter(|probe| probe.wdl == best).collect();
+        if best != Wdl::Draw && moves.iter().all(|probe| probe.dtz.is_some()){
+            //a winning dtz is positive and a losing one negative, the smallest is wanted in both cases
+            let quickest = moves.iter().filter_map(|probe| probe.dtz).min();
+            moves.retain(|probe| probe.dtz == quickest);
+        }
+        Some(moves.into_iter().map(|probe| probe.mv).collect())
+    }
+
+    //the tables do not bother storing results that a capture (or, with
+    //`zeroing`, a pawn move) can improve on, so those are tried first;
+    //the flag says whether the best move is such a move
+    fn search_wdl(&self,position:&Position,zeroing:bool)->Result<(Wdl,bool),Failure>{
+        let moves = position.legal_moves();
+        let mut best = Wdl::Loss;
+        let mut tried = 0;
+        for mv in moves.iter(){
+            let pawn = position.piece_at(mv.from).map(|p| p.kind) == Some(PieceKind::Pawn);
+            if !(position.is_capture(mv) || zeroing && pawn){
+                continue
+            }
+            tried+=1;
+            let mut next = *position;
+            next.play(mv);
+            let value = self.search_wdl(&next,false)?.0.opposite();
+            if value > best{
+                best = value;
+                if value == Wdl::Win{
+                    return Ok((value,true))
+                }
+            }
+        }
+        //nothing but captures, the stored value could be anything
+        let no_more_moves = tried > 0 && tried == moves.len();
+        let value = if no_more_moves {best} else {Wdl::from_value(self.probe_table(position,Kind::Wdl,Wdl::Draw)?)};
+        if best >= value{
+            return Ok((best,best > Wdl::Draw || no_more_moves))
+        }
+        Ok((value,false))
+    }
+
+    fn dtz(&self,position:&Position)->Result<i32,Failure>{
+        let (wdl,zeroing) = self.search_wdl(position,true)?;
+        if wdl == Wdl::Draw{
+            return Ok(0)
+        }
+        if zeroing{
+            return Ok(dtz_before_zeroing(wdl))
+        }
+        match self.probe_table(position,Kind::Dtz,wdl){
+            Ok(dtz) => {
+                let cursed = wdl == Wdl::CursedWin || wdl == Wdl::BlessedLoss;
+                return Ok((dtz+if cursed {100} else {0})*wdl.signum())
+            }
+            Err(Failure::Missing) => return Err(Failure::Missing),
+            Err(Failure::OtherSide) => {}
+        }
+        //the table has the other side to move: best reply one ply deeper
+        let mut best = i32::MAX;
+        for mv in position.legal_moves(){
+            let zeroing = is_zeroing(position,&mv);
+            let mut next = *position;
+            next.play(&mv);
+            let mut dtz = if zeroing{
+                -dtz_before_zeroing(self.search_wdl(&next,false)?.0)
+            }
+            else{
+                -self.dtz(&next)?
+            };
+            if dtz == 1 && next.is_checkmate(){
+                best = 1;
+            }
+            if !zeroing{
+                dtz+=dtz.signum();
+            }
+            if dtz < best && dtz.signum() == wdl.signum(){
+                best = dtz;
+            }
+        }
+        //no legal moves: mated
+        Ok(if best == i32::MAX {-1} else {best})
+    }
+
+    //the raw value stored for the position: the WDL value from -2 to 2, or
+    //the DTZ in plies for a game known to end in `wdl`
+    fn probe_table(&self,position:&Position,kind:Kind,wdl:Wdl)->Result<i32,Failure>{
+        //king against king
+        if position.pieces().count() == 2{
+            return Ok(0)
+        }
+        //tables are named with the stronger side first and computed as if it
+        //were white
+        let white_first = material_name(position,Color::White);
+        let black_first = material_name(position,Color::Black);
+        let (table,black_stronger) = match self.table(&white_first,kind){
+            Some(table) => (table,false),
+            None => (self.table(&black_first,kind).ok_or(Failure::Missing)?,true),
+        };
+        let (stm,file,index) = table.locate(position,black_stronger);
+        let pairs = &table.pairs[stm % table.pairs.len()][file];
+        let one_sided = !table.symmetric || table.has_pawns;
+        if kind == Kind::Dtz && one_sided && (pairs.flags & 1) as usize != stm{
+            return Err(Failure::OtherSide)
+        }
+        let value = table.decompress(pairs,index) as i32;
+        Ok(match kind{
+            Kind::Wdl => value-2,
+            Kind::Dtz => table.dtz_value(pairs,value,wdl),
+        })
+    }
+
+    fn table(&self,name:&str,kind:Kind)->Option<Arc<Table>>{
+        let found = match kind{
+            Kind::Wdl => &self.wdl,
+            Kind::Dtz => &self.dtz,
+        };
+        if !found.contains(name){
+            return None
+        }
+        let mut loaded = self.loaded.lock().unwrap();
+        loaded.entry((name.to_string(),kind))
+            .or_insert_with(|| {
+                let path = self.directory.join(format!("{}.{}",name,kind.extension()));
+                let data = std::fs::read(path).ok()?;
+                Table::parse(name,kind,data).ok().map(Arc::new)
+            })
+            .clone()
+    }
+}
+
+fn is_zeroing(position:&Position,mv:&Move)->bool{
+    position.is_capture(mv) || position.piece_at(mv.from).map(|p| p.kind) == Some(PieceKind::Pawn)
+}
+
+//DTZ of a position whose best move is a capture or pawn move
+fn dtz_before_zeroing(wdl:Wdl)->i32{
+    match wdl{
+        Wdl::Loss => -1,
+        Wdl::BlessedLoss => -101,
+        Wdl::Draw => 0,
+        Wdl::CursedWin => 101,
+        Wdl::Win => 1,
+    }
+}
+
+//"KRPvKR": pieces strongest first, the given side before the 'v'
+fn material_name(position:&Position,first:Color)->String{
+    let side = |color:Color| {
+        let mut letters = String::new();
+        for kind in NAME_ORDER{
+            let count = position.pieces().filter(|(_,piece)| *piece == Piece::new(kind,color)).count();
+            for _ in 0..count{
+                letters.push(kind.letter().to_ascii_uppercase());
+            }
+        }
+        letters
+    };
+    format!("{}v{}",side(first),side(first.opposite()))
+}
+
+const NAME_ORDER:[PieceKind;6] = [
+    PieceKind::King,
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+    PieceKind::Pawn,
+];
+
+//how the tables number pieces: 1 pawn ... 6 king, plus 8 for black
+fn code(piece:&Piece)->u8{
+    let kind = match piece.kind{
+        PieceKind::Pawn => 1,
+        PieceKind::Knight => 2,
+        PieceKind::Bishop => 3,
+        PieceKind::Rook => 4,
+        PieceKind::Queen => 5,
+        PieceKind::King => 6,
+    };
+    match piece.color{
+        Color::White => kind,
+        Color::Black => kind+8,
+    }
+}
+
+fn edge_distance(file:usize)->usize{
+    file.min(7-file)
+}
+
+//which pieces a table is for, from its name
+struct Material{
+    //piece counts by code, see code()
+    counts:[usize;16],
+}
+
+impl Material{
+    fn parse(name:&str)->Option<Material>{
+        let (white,black) = name.split_once('v')?;
+        if !white.starts_with('K') || !black.starts_with('K') || white.len()+black.len() > MAX_PIECES{
+            return None
+        }
+        let mut counts = [0;16];
+        for (side,color) in [(white,Color::White),(black,Color::Black)]{
+            for letter in side.chars(){
+                if !letter.is_ascii_uppercase(){
+                    return None
+                }
+                let kind = PieceKind::from_letter(letter)?;
+                counts[code(&Piece::new(kind,color)) as usize]+=1;
+            }
+        }
+        if counts[6] != 1 || counts[14] != 1{
+            return None
+        }
+        Some(Material{counts})
+    }
+}
+
+//decoding of one table (a side to move, and with pawns a file of the leading pawn)
+#[derive(Default)]
+struct Pairs{
+    flags:u8,
+    //piece codes in the order the table encodes them
+    pieces:[u8;MAX_PIECES],
+    //pieces encoded together and what each group's index is multiplied by;
+    //the last factor is the table size
+    group_len:Vec<usize>,
+    group_factor:Vec<u64>,
+    //what a single value table holds, otherwise the shortest code length
+    min_sym_len:u8,
+    block_size:usize,
+    span:u64,
+    //byte offsets into the file
+    sparse_index:usize,
+    sparse_index_size:usize,
+    block_lengths:usize,
+    block_lengths_size:usize,
+    blocks:usize,
+    num_blocks:usize,
+    lowest_sym:usize,
+    btree:usize,
+    //canonical Huffman code limits by length, left aligned
+    base64:Vec<u64>,
+    //number of values each symbol stands for, minus one
+    symlen:Vec<u32>,
+    //DTZ only: where the four value maps start, see dtz_value
+    map:[usize;4],
+}
+
+const SINGLE_VALUE:u8 = 0x80;
+//DTZ flags
+const STORED_MAPPED:u8 = 0x02;
+const WIN_PLIES:u8 = 0x04;
+const LOSS_PLIES:u8 = 0x08;
+const WIDE_MAP:u8 = 0x10;
+
+struct Table{
+    data:Vec<u8>,
+    has_pawns:bool,
+    //any piece besides the kings without a twin
+    has_unique_pieces:bool,
+    //pawns of the color the table leads with, and of the other color
+    pawn_count:[usize;2],
+    piece_count:usize,
+    symmetric:bool,
+    //[side to move][file]: two sides in WDL tables with unequal material,
+    //four files with pawns
+    pairs:Vec<Vec<Pairs>>,
+}
+
+impl Table{
+    fn parse(name:&str,kind:Kind,data:Vec<u8>)->Result<Table,String>{
+        let material = Material::parse(name).ok_or(format!("{} is not a table name",name))?;
+        let magic = match kind{
+            Kind::Wdl => WDL_MAGIC,
+            Kind::Dtz => DTZ_MAGIC,
+        };
+        if data.len() < 5 || data[0..4] != magic{
+            return Err(format!("{} is not a Syzygy {} file",name,kind.extension()))
+        }
+        let counts = material.counts;
+        let white_pawns = counts[1];
+        let black_pawns = counts[9];
+        //the side with fewer pawns leads, it compresses better
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        let (white,black) = name.split_once('v').unwrap_or_default();
+        let mut table = Table{
+            data:Vec::new(),
+            has_pawns:white_pawns+black_pawns > 0,
+            has_unique_pieces:(1..6).chain(9..14).any(|code| counts[code] == 1),
+            pawn_count:if white_leads {[white_pawns,black_pawns]} else {[black_pawns,white_pawns]},
+            piece_count:name.len()-1,
+            symmetric:white == black,
+            pairs:Vec::new(),
+        };
+        if (data[4] & 2 != 0) != table.has_pawns{
+            return Err(format!("{}: pawn flag does not match the name",name))
+        }
+        let sides = if kind == Kind::Wdl && !table.symmetric {2} else {1};
+        let files = if table.has_pawns {4} else {1};
+        let both_pawns = table.has_pawns && table.pawn_count[1] > 0;
+        table.pairs = (0..sides).map(|_| (0..files).map(|_| Pairs::default()).collect()).collect();
+
+        let mut reader = Reader{data:&data,at:5};
+        for file in 0..files{
+            let order = reader.byte();
+            let order_pawns = if both_pawns {reader.byte()} else {0xff};
+            let orders = [
+                [order & 0xf,if both_pawns {order_pawns & 0xf} else {0xf}],
+                [order >> 4,if both_pawns {order_pawns >> 4} else {0xf}],
+            ];
+            for k in 0..table.piece_count{
+                let byte = reader.byte();
+                for side in 0..sides{
+                    table.pairs[side][file].pieces[k] = if side == 1 {byte >> 4} else {byte & 0xf};
+                }
+            }
+            for (side,order) in orders.iter().enumerate().take(sides){
+                table.check_pieces(side,file,&counts).map_err(|e| format!("{}: {}",name,e))?;
+                table.set_groups(side,file,*order).map_err(|e| format!("{}: {}",name,e))?;
+            }
+        }
+        reader.align(2);
+        for file in 0..files{
+            for side in 0..sides{
+                table.set_sizes(side,file,&mut reader).map_err(|e| format!("{}: {}",name,e))?;
+            }
+        }
+        if kind == Kind::Dtz{
+            for file in 0..files{
+                let pairs = &mut table.pairs[0][file];
+                if pairs.flags & STORED_MAPPED == 0{
+                    continue
+                }
+                if pairs.flags & WIDE_MAP != 0{
+                    reader.align(2);
+                    for map in pairs.map.iter_mut(){
+                        let len = reader.u16();
+                        *map = reader.at;
+                        reader.at+=2*len as usize;
+                    }
+                }
+                else{
+                    for map in pairs.map.iter_mut(){
+                        let len = reader.byte();
+                        *map = reader.at;
+                        reader.at+=len as usize;
+                    }
+                }
+            }
+            reader.align(2);
+        }
+        for file in 0..files{
+            for side in 0..sides{
+                let pairs = &mut table.pairs[side][file];
+                pairs.sparse_index = reader.at;
+                reader.at+=6*pairs.sparse_index_size;
+            }
+        }
+        for file in 0..files{
+            for side in 0..sides{
+                let pairs = &mut table.pairs[side][file];
+                pairs.block_lengths = reader.at;
+                reader.at+=2*pairs.block_lengths_size;
+            }
+        }
+        for file in 0..files{
+            for side in 0..sides{
+                let pairs = &mut table.pairs[side][file];
+                //single value tables have no blocks to line up
+                if pairs.num_blocks > 0{
+                    reader.align(64);
+                }
+                pairs.blocks = reader.at;
+                let blocks = pairs.num_blocks.checked_mul(pairs.block_size);
+                reader.at = blocks.and_then(|len| reader.at.checked_add(len)).unwrap_or(usize::MAX);
+            }
+        }
+        if reader.at > data.len(){
+            return Err(format!("{}.{} is cut short",name,kind.extension()))
+        }
+        table.data = data;
+        Ok(table)
+    }
+
+    //the pieces listed have to be the ones in the name, the pawns of one
+    //color leading every part of the table and the other color's next
+    fn check_pieces(&self,side:usize,file:usize,counts:&[usize;16])->Result<(),String>{
+        let pieces = &self.pairs[side][file].pieces[..self.piece_count];
+        let mut listed = [0;16];
+        for piece in pieces{
+            listed[*piece as usize]+=1;
+        }
+        if listed != *counts{
+            return Err("the pieces listed do not match the name".to_string())
+        }
+        if !self.has_pawns{
+            return Ok(())
+        }
+        let lead = pieces[0];
+        let [lead_pawns,other_pawns] = self.pawn_count;
+        let in_order = lead & 7 == 1 && lead == self.pairs[0][0].pieces[0]
+            && pieces[..lead_pawns].iter().all(|piece| *piece == lead)
+            && pieces[lead_pawns..lead_pawns+other_pawns].iter().all(|piece| *piece == lead^8);
+        if !in_order{
+            return Err("the pawns are not listed first".to_string())
+        }
+        Ok(())
+    }
+
+    //splits the pieces into groups encoded together (the leading group: up
+    //to three unique pieces, the kings, or the leading pawns; then pieces of
+    //the same kind and color) and works out each group's factor in the
+    //order the table gives
+    fn set_groups(&mut self,side:usize,file:usize,order:[u8;2])->Result<(),String>{
+        let has_pawns = self.has_pawns;
+        let both_pawns = has_pawns && self.pawn_count[1] > 0;
+        let unique = self.has_unique_pieces;
+        let piece_count = self.piece_count;
+        let pairs = &mut self.pairs[side][file];
+
+        let mut first_len:i32 = if has_pawns {0} else if unique {3} else {2};
+        let mut group_len = vec![1];
+        for i in 1..piece_count{
+            first_len-=1;
+            if first_len > 0 || pairs.pieces[i] == pairs.pieces[i-1]{
+                *group_len.last_mut().unwrap()+=1;
+            }
+            else{
+                group_len.push(1);
+            }
+        }
+        let groups = group_len.len();
+        let order_fits = (order[0] as usize) < groups
+            && if both_pawns {(order[1] as usize) < groups && order[1] != order[0]} else {true};
+        if !order_fits || (has_pawns && group_len[0] >= LEAD_PAWNS_SIZE.len()){
+            return Err("bad group order".to_string())
+        }
+        let mut factor = vec![0;groups+1];
+        let mut next = if both_pawns {2} else {1};
+        let mut free = 64-group_len[0]-if both_pawns {group_len.get(1).copied().unwrap_or(0)} else {0};
+        let mut size:u64 = 1;
+        let mut k = 0;
+        while next < groups || k == order[0] || k == order[1]{
+            if k == order[0]{
+                factor[0] = size;
+                size*=if has_pawns {LEAD_PAWNS_SIZE[group_len[0]][file]}
+                    else if unique {31332}
+                    else {462};
+            }
+            else if k == order[1]{
+                factor[1] = size;
+                size*=binomial(group_len.get(1).copied().unwrap_or(0),48-group_len[0]);
+            }
+            else{
+                factor[next] = size;
+                size*=binomial(group_len[next],free);
+                free-=group_len[next];
+                next+=1;
+            }
+            k+=1;
+        }
+        factor[groups] = size;
+        pairs.group_len = group_len;
+        pairs.group_factor = factor;
+        Ok(())
+    }
+
+    fn set_sizes(&mut self,side:usize,file:usize,reader:&mut Reader)->Result<(),String>{
+        let pairs = &mut self.pairs[side][file];
+        pairs.flags = reader.byte();
+        if pairs.flags & SINGLE_VALUE != 0{
+            pairs.min_sym_len = reader.byte();
+            return Ok(())
+        }
+        let size = *pairs.group_factor.last().unwrap_or(&0);
+        let block_size = 1usize.checked_shl(reader.byte() as u32);
+        let span = 1u64.checked_shl(reader.byte() as u32);
+        let (Some(block_size),Some(span)) = (block_size,span) else{
+            return Err("block size or span out of range".to_string())
+        };
+        pairs.block_size = block_size;
+        pairs.span = span;
+        pairs.sparse_index_size = size.div_ceil(pairs.span) as usize;
+        let padding = reader.byte() as usize;
+        pairs.num_blocks = reader.u32() as usize;
+        pairs.block_lengths_size = pairs.num_blocks+padding;
+        let max_sym_len = reader.byte();
+        pairs.min_sym_len = reader.byte();
+        //codes are read 32 bits at a time
+        if max_sym_len > 32 || pairs.min_sym_len > max_sym_len{
+            return Err("bad code lengths".to_string())
+        }
+        pairs.lowest_sym = reader.at;
+        let lengths = (max_sym_len as usize+1).saturating_sub(pairs.min_sym_len as usize);
+        let lowest = |i:usize| reader.data.u16_at(pairs.lowest_sym+2*i) as u64;
+        //canonical Huffman: longer codes have lower values, so each length's
+        //lowest code padded to 64 bits marks where that length starts
+        let mut base64 = vec![0u64;lengths];
+        for i in (0..lengths.saturating_sub(1)).rev(){
+            base64[i] = (base64[i+1]+lowest(i)).wrapping_sub(lowest(i+1))/2;
+        }
+        for (i,base) in base64.iter_mut().enumerate(){
+            *base = base.checked_shl((64-i-pairs.min_sym_len as usize) as u32).unwrap_or(0);
+        }
+        pairs.base64 = base64;
+        reader.at+=2*lengths;
+        let symbols = reader.u16() as usize;
+        pairs.btree = reader.at;
+        //symbols stand for pairs of other symbols (recursive pairing)
+        let mut symlen = vec![0;symbols];
+        let mut visited = vec![false;symbols];
+        for sym in 0..symbols{
+            set_symlen(reader.data,pairs.btree,sym,&mut symlen,&mut visited);
+        }
+        //a pair standing for fewer values than its halves points back up
+        //the tree, decompress would go round in circles
+        for sym in 0..symbols{
+            if btree_right(reader.data,pairs.btree,sym) == 0xfff{
+                continue
+            }
+            let left = btree_left(reader.data,pairs.btree,sym);
+            let right = btree_right(reader.data,pairs.btree,sym);
+            let halves = |left:usize,right:usize| symlen[left].checked_add(symlen[right])?.checked_add(1);
+            if left >= symbols || right >= symbols || halves(left,right) != Some(symlen[sym]){
+                return Err("broken symbol tree".to_string())
+            }
+        }
+        pairs.symlen = symlen;
+        reader.at+=3*symbols+(symbols & 1);
+        Ok(())
+    }
+
+    //side to move and file of the leading pawn as the table sees them, and
+    //the position's index there; with black stronger (or black to move in a
+    //symmetric table) colors and ranks are swapped
+    fn locate(&self,position:&Position,black_stronger:bool)->(usize,usize,u64){
+        let flip = black_stronger || (self.symmetric && position.side_to_move() == Color::Black);
+        let (flip_color,flip_squares) = if flip {(8,56)} else {(0,0)};
+        let stm = flip as usize^position.side_to_move().index();
+
+        let pieces:Vec<(usize,u8)> = position.pieces()
+            .map(|(sq,piece)| (sq.index()^flip_squares,code(&piece)^flip_color))
+            .collect();
+        let mut squares:Vec<usize> = Vec::with_capacity(pieces.len());
+        let mut codes:Vec<u8> = Vec::with_capacity(pieces.len());
+        let lead = self.pairs[0][0].pieces[0];
+        let mut file = 0;
+        if self.has_pawns{
+            //the pawns of the color the table leads with come first, the one
+            //nearest the edge and then lowest in front
+            squares.extend(pieces.iter().filter(|(_,code)| *code == lead).map(|(sq,_)| *sq));
+            let front = (0..squares.len()).max_by_key(|i| MAP_PAWNS[squares[*i]]).unwrap_or(0);
+            squares.swap(0,front);
+            file = edge_distance(squares[0]%8);
+            codes.resize(squares.len(),lead);
+        }
+        let lead_pawns = squares.len();
+        for (sq,code) in pieces.iter(){
+            if !(self.has_pawns && *code == lead){
+                squares.push(*sq);
+                codes.push(*code);
+            }
+        }
+        let pairs = &self.pairs[stm % self.pairs.len()][file];
+        //same order as the table's pieces
+        for i in lead_pawns..codes.len().saturating_sub(1){
+            for j in i+1..codes.len(){
+                if pairs.pieces[i] == codes[j]{
+                    codes.swap(i,j);
+                    squares.swap(i,j);
+                    break
+                }
+            }
+        }
+        (stm,file,self.index(pairs,&mut squares,lead_pawns))
+    }
+
+    //squares are in the table's piece order, the `lead_pawns` first
+    fn index(&self,pairs:&Pairs,squares:&mut [usize],lead_pawns:usize)->u64{
+        //mirror so the leading piece is on files a-d
+        if squares[0]%8 > 3{
+            for sq in squares.iter_mut(){
+                *sq^=7;
+            }
+        }
+        let mut index;
+        if self.has_pawns{
+            index = LEAD_PAWN_INDEX[lead_pawns][squares[0]];
+            squares[1..lead_pawns].sort_by_key(|sq| MAP_PAWNS[*sq]);
+            for i in 1..lead_pawns{
+                index+=binomial(i,MAP_PAWNS[squares[i]] as usize);
+            }
+        }
+        else{
+            //and below the 5th rank
+            if squares[0]/8 > 3{
+                for sq in squares.iter_mut(){
+                    *sq^=56;
+                }
+            }
+            //and the first leading piece off the a1-h8 diagonal below it
+            for i in 0..pairs.group_len[0]{
+                let diagonal = off_diagonal(squares[i]);
+                if diagonal == 0{
+                    continue
+                }
+                if diagonal > 0{
+                    for sq in squares[i..].iter_mut(){
+                        *sq = ((*sq >> 3) | (*sq << 3)) & 63;
+                    }
+                }
+                break
+            }
+            if self.has_unique_pieces{
+                let (s0,s1,s2) = (squares[0],squares[1],squares[2]);
+                let adjust1 = (s1 > s0) as u64;
+                let adjust2 = (s2 > s0) as u64+(s2 > s1) as u64;
+                let (s0,s1,s2) = (s0 as u64,s1 as u64,s2 as u64);
+                index = if off_diagonal(squares[0]) != 0{
+                    (MAP_A1D1D4[squares[0]] as u64*63+s1-adjust1)*62+s2-adjust2
+                }
+                else if off_diagonal(squares[1]) != 0{
+                    (6*63+(s0 >> 3)*28+MAP_B1H1H7[squares[1]] as u64)*62+s2-adjust2
+                }
+                else if off_diagonal(squares[2]) != 0{
+                    6*63*62+4*28*62+(s0 >> 3)*7*28+((s1 >> 3)-adjust1)*28+MAP_B1H1H7[squares[2]] as u64
+                }
+                else{
+                    6*63*62+4*28*62+4*7*28+(s0 >> 3)*7*6+((s1 >> 3)-adjust1)*6+(s2 >> 3)-adjust2
+                };
+            }
+            else{
+                index = MAP_KK[MAP_A1D1D4[squares[0]] as usize][squares[1]] as u64;
+            }
+        }
+        index*=pairs.group_factor[0];
+
+        //the other groups: each square counted among the ones the earlier groups left free
+        let mut start = pairs.group_len[0];
+        let mut other_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        for group in 1..pairs.group_len.len(){
+            let len = pairs.group_len[group];
+            squares[start..start+len].sort();
+            let mut n = 0;
+            for i in 0..len{
+                let sq = squares[start+i];
+                let adjust = squares[..start].iter().filter(|earlier| sq > **earlier).count();
+                let free = sq-adjust-if other_pawns {8} else {0};
+                n+=binomial(i+1,free);
+            }
+            other_pawns = false;
+            index+=n*pairs.group_factor[group];
+            start+=len;
+        }
+        index
+    }
+
+    //the value at `index`: blocks of Huffman coded symbols, each symbol
+    //standing for one or (through pairing) several values in a row
+    fn decompress(&self,pairs:&Pairs,index:u64)->u16{
+        if pairs.flags & SINGLE_VALUE != 0{
+            return pairs.min_sym_len as u16
+        }
+        let data = &self.data[..];
+        //the sparse index knows the block and offset of every span'th value,
+        //from there it is a walk over the block lengths
+        let k = ((index/pairs.span) as usize).min(pairs.sparse_index_size.saturating_sub(1));
+        let mut block = (data.u32_at(pairs.sparse_index+6*k) as usize).min(pairs.block_lengths_size.saturating_sub(1));
+        let mut offset = data.u16_at(pairs.sparse_index+6*k+4) as i64;
+        offset+=(index%pairs.span) as i64-(pairs.span/2) as i64;
+        let block_length = |block:usize| data.u16_at(pairs.block_lengths+2*block) as i64;
+        while offset < 0 && block > 0{
+            block-=1;
+            offset+=block_length(block)+1;
+        }
+        while offset > block_length(block) && block+1 < pairs.block_lengths_size{
+            offset-=block_length(block)+1;
+            block+=1;
+        }
+
+        let mut at = pairs.blocks.saturating_add(block.saturating_mul(pairs.block_size)).min(data.len());
+        let mut buffer = data.u64_be_at(at);
+        at+=8;
+        let mut buffered = 64;
+        let min_len = pairs.min_sym_len as usize;
+        let symlen = |sym:usize| pairs.symlen.get(sym).copied().unwrap_or(0) as i64;
+        let mut sym;
+        loop{
+            let mut len = 0;
+            while len+1 < pairs.base64.len() && buffer < pairs.base64[len]{
+                len+=1;
+            }
+            sym = (buffer-pairs.base64[len]).checked_shr((64-len-min_len) as u32).unwrap_or(0) as usize;
+            sym+=data.u16_at(pairs.lowest_sym+2*len) as usize;
+            if offset < symlen(sym)+1{
+                break
+            }
+            offset-=symlen(sym)+1;
+            let bits = len+min_len;
+            buffer <<= bits;
+            buffered-=bits;
+            if buffered <= 32{
+                buffered+=32;
+                buffer|=(data.u32_be_at(at) as u64) << (64-buffered);
+                at+=4;
+            }
+        }
+        //down the pairing tree to the single value
+        while symlen(sym) != 0{
+            let left = btree_left(data,pairs.btree,sym);
+            if offset < symlen(left)+1{
+                sym = left;
+            }
+            else{
+                offset-=symlen(left)+1;
+                sym = btree_right(data,pairs.btree,sym);
+            }
+        }
+        btree_left(data,pairs.btree,sym) as u16
+    }
+
+    //DTZ tables may store an index into one of four maps (by result) instead
+    //of the value, and moves instead of plies; this gives plies
+    fn dtz_value(&self,pairs:&Pairs,value:i32,wdl:Wdl)->i32{
+        let mut value = value;
+        if pairs.flags & STORED_MAPPED != 0{
+            let map = pairs.map[match wdl{
+                Wdl::Loss => 1,
+                Wdl::BlessedLoss => 3,
+                Wdl::CursedWin => 2,
+                Wdl::Draw | Wdl::Win => 0,
+            }];
+            value = if pairs.flags & WIDE_MAP != 0{
+                self.data.u16_at(map+2*value as usize) as i32
+            }
+            else{
+                self.data.get(map+value as usize).copied().unwrap_or(0) as i32
+            };
+        }
+        let in_moves = match wdl{
+            Wdl::Win => pairs.flags & WIN_PLIES == 0,
+            Wdl::Loss => pairs.flags & LOSS_PLIES == 0,
+            Wdl::CursedWin | Wdl::BlessedLoss => true,
+            Wdl::Draw => false,
+        };
+        if in_moves{
+            value*=2;
+        }
+        value+1
+    }
+}
+
+//counts the values a symbol stands for, its two halves first
+fn set_symlen(data:&[u8],btree:usize,sym:usize,symlen:&mut [u32],visited:&mut [bool]){
+    if sym >= symlen.len() || visited[sym]{
+        return
+    }
+    visited[sym] = true;
+    let right = btree_right(data,btree,sym);
+    if right == 0xfff{
+        return
+    }
+    let left = btree_left(data,btree,sym);
+    set_symlen(data,btree,left,symlen,visited);
+    set_symlen(data,btree,right,symlen,visited);
+    let len = |sym:usize| symlen.get(sym).copied().unwrap_or(0);
+    symlen[sym] = len(left).saturating_add(len(right)).saturating_add(1);
+}
+
+//each symbol is 3 bytes: two 12 bit halves; a right half of 0xfff marks a
+//single value, kept in the left half
+fn btree_left(data:&[u8],btree:usize,sym:usize)->usize{
+    let at = btree+3*sym;
+    let byte = |i:usize| data.get(at+i).copied().unwrap_or(0) as usize;
+    ((byte(1) & 0xf) << 8) | byte(0)
+}
+
+fn btree_right(data:&[u8],btree:usize,sym:usize)->usize{
+    let at = btree+3*sym;
+    let byte = |i:usize| data.get(at+i).copied().unwrap_or(0) as usize;
+    (byte(2) << 4) | (byte(1) >> 4)
+}
+
+//reads past the end give zeros; the last block of a table may be short
+trait Bytes{
+    fn bytes_at<const N:usize>(&self,at:usize)->[u8;N];
+
+    fn u16_at(&self,at:usize)->u16{
+        u16::from_le_bytes(self.bytes_at(at))
+    }
+
+    fn u32_at(&self,at:usize)->u32{
+        u32::from_le_bytes(self.bytes_at(at))
+    }
+
+    fn u32_be_at(&self,at:usize)->u32{
+        u32::from_be_bytes(self.bytes_at(at))
+    }
+
+    fn u64_be_at(&self,at:usize)->u64{
+        u64::from_be_bytes(self.bytes_at(at))
+    }
+}
+
+impl Bytes for [u8]{
+    fn bytes_at<const N:usize>(&self,at:usize)->[u8;N]{
+        let mut bytes = [0;N];
+        for (i,byte) in bytes.iter_mut().enumerate(){
+            *byte = self.get(at+i).copied().unwrap_or(0);
+        }
+        bytes
+    }
+}
+
+//walks a table file's header
+struct Reader<'a>{
+    data:&'a [u8],
+    at:usize,
+}
+
+impl<'a> Reader<'a>{
+    fn byte(&mut self)->u8{
+        let byte = self.data.get(self.at).copied().unwrap_or(0);
+        self.at+=1;
+        byte
+    }
+
+    fn u16(&mut self)->u16{
+        let value = self.data.u16_at(self.at);
+        self.at+=2;
+        value
+    }
+
+    fn u32(&mut self)->u32{
+        let value = self.data.u32_at(self.at);
+        self.at+=4;
+        value
+    }
+
+    fn align(&mut self,to:usize){
+        self.at = self.at.div_ceil(to)*to;
+    }
+}
+
+//rank minus file: above the a1-h8 diagonal when positive
+fn off_diagonal(sq:usize)->i32{
+    (sq/8) as i32-(sq%8) as i32
+}
+
+fn binomial(k:usize,n:usize)->u64{
+    if k > n{
+        return 0
+    }
+    BINOMIAL[k][n]
+}
+
+//BINOMIAL[k][n] = n choose k
+const BINOMIAL:[[u64;64];MAX_PIECES+1] = binomials();
+
+const fn binomials()->[[u64;64];MAX_PIECES+1]{
+    let mut table = [[0;64];MAX_PIECES+1];
+    let mut k = 0;
+    while k <= MAX_PIECES{
+        let mut n = 0;
+        while n < 64{
+            let mut numerator:u64 = 1;
+            let mut denominator:u64 = 1;
+            let mut i = 0;
+            while i < k{
+                numerator*=(n as u64).wrapping_sub(i as u64);
+                denominator*=i as u64+1;
+                i+=1;
+            }
+            table[k][n] = if n < k {0} else {numerator/denominator};
+            n+=1;
+        }
+        k+=1;
+    }
+    table
+}
+
+//squares below the a1-h8 diagonal, 0 to 27
+const MAP_B1H1H7:[u8;64] = map_b1h1h7();
+
+const fn map_b1h1h7()->[u8;64]{
+    let mut map = [0;64];
+    let mut code = 0;
+    let mut sq = 0;
+    while sq < 64{
+        if off_diagonal_const(sq) < 0{
+            map[sq] = code;
+            code+=1;
+        }
+        sq+=1;
+    }
+    map
+}
+
+//the a1-d1-d4 triangle, 0 to 5 below the diagonal and 6 to 9 on it
+const MAP_A1D1D4:[u8;64] = map_a1d1d4();
+
+const fn map_a1d1d4()->[u8;64]{
+    let mut map = [0;64];
+    let mut code = 0;
+    let mut sq = 0;
+    while sq <= 27{
+        if off_diagonal_const(sq) < 0 && sq%8 <= 3{
+            map[sq] = code;
+            code+=1;
+        }
+        sq+=1;
+    }
+    sq = 0;
+    while sq <= 27{
+        if off_diagonal_const(sq) == 0 && sq%8 <= 3{
+            map[sq] = code;
+            code+=1;
+        }
+        sq+=1;
+    }
+    map
+}
+
+//the 462 legal placements of two kings with the first one in the a1-d1-d4
+//triangle (and the second not above the diagonal when the first is on it);
+//both on the diagonal come last
+const MAP_KK:[[u16;64];10] = map_kk();
+
+const fn map_kk()->[[u16;64];10]{
+    let mut map = [[0;64];10];
+    let mut code = 0;
+    let mut pass = 0;
+    while pass < 2{
+        let mut index = 0;
+        while index < 10{
+            let mut s1 = 0;
+            while s1 <= 27{
+                if MAP_A1D1D4[s1] as usize == index && (index != 0 || s1 == 1){
+                    let mut s2 = 0;
+                    while s2 < 64{
+                        let file_gap = (s1%8) as i32-(s2%8) as i32;
+                        let rank_gap = (s1/8) as i32-(s2/8) as i32;
+                        let touching = file_gap >= -1 && file_gap <= 1 && rank_gap >= -1 && rank_gap <= 1;
+                        let first_on = off_diagonal_const(s1) == 0;
+                        let second = off_diagonal_const(s2);
+                        let both_on = first_on && second == 0;
+                        let skipped = touching || (first_on && second > 0);
+                        if !skipped && both_on == (pass == 1){
+                            map[index][s2] = code;
+                            code+=1;
+                        }
+                        s2+=1;
+                    }
+                }
+                s1+=1;
+            }
+            index+=1;
+        }
+        pass+=1;
+    }
+    map
+}
+
+const fn off_diagonal_const(sq:usize)->i32{
+    (sq/8) as i32-(sq%8) as i32
+}
+
+//pawns from a2 to h7 numbered so the leading pawn, nearest the edge and
+//then lowest, has the highest number
+const MAP_PAWNS:[u8;64] = lead_pawn_tables().0;
+//index of the leading pawn's square for 1 to 5 leading pawns
+const LEAD_PAWN_INDEX:[[u64;64];6] = lead_pawn_tables().1;
+//number of leading pawn placements by count and file of the leading pawn
+const LEAD_PAWNS_SIZE:[[u64;4];6] = lead_pawn_tables().2;
+
+const fn lead_pawn_tables()->([u8;64],[[u64;64];6],[[u64;4];6]){
+    let mut map = [0u8;64];
+    let mut index = [[0u64;64];6];
+    let mut size = [[0u64;4];6];
+    let mut available = 47;
+    let mut count = 1;
+    while count <= 5{
+        let mut file = 0;
+        while file < 4{
+            let mut idx = 0;
+            let mut rank = 1;
+            while rank <= 6{
+                let sq = rank*8+file;
+                if count == 1{
+                    map[sq] = available;
+                    available-=1;
+                    map[sq^7] = available;
+                    available = available.saturating_sub(1);
+                }
+                index[count][sq] = idx;
+                idx+=BINOMIAL[count-1][map[sq] as usize];
+                rank+=1;
+            }
+            size[count][file] = idx;
+            file+=1;
+        }
+        count+=1;
+    }
+    (map,index,size)
+}
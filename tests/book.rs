@@ -0,0 +1,69 @@
+use chess::board::{Move, Position, Square};
+use chess::book::{Book, BookChoice};
+
+//one 16 byte Polyglot entry
+fn entry(key:u64,from:&str,to:&str,promotion:u16,weight:u16)->Vec<u8>{
+    let from = Square::from_name(from).unwrap();
+    let to = Square::from_name(to).unwrap();
+    let mv = promotion << 12
+        | (from.rank() as u16) << 9 | (from.file() as u16) << 6
+        | (to.rank() as u16) << 3 | to.file() as u16;
+    let mut bytes = key.to_be_bytes().to_vec();
+    bytes.extend_from_slice(&mv.to_be_bytes());
+    bytes.extend_from_slice(&weight.to_be_bytes());
+    bytes.extend_from_slice(&0u32.to_be_bytes());
+    bytes
+}
+
+fn mv(text:&str)->Move{
+    Move::from_uci(text).unwrap()
+}
+
+fn book(mut entries:Vec<Vec<u8>>)->Book{
+    entries.sort();
+    Book::from_bytes(&entries.concat()).unwrap()
+}
+
+#[test]
+fn looks_up_moves_by_key(){
+    let start = Position::startpos();
+    let castle = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let book = book(vec![
+        entry(start.zobrist(),"e2","e4",0,10),
+        entry(start.zobrist(),"d2","d4",0,30),
+        //not legal in the position, left out
+        entry(start.zobrist(),"e2","e5",0,50),
+        entry(castle.zobrist(),"e1","h1",0,1),
+        entry(castle.zobrist(),"e1","a1",0,1),
+    ]);
+    let moves:Vec<(Move,u16)> = book.moves(&start).iter().map(|entry| (entry.mv,entry.weight)).collect();
+    assert_eq!(moves,vec![(mv("d2d4"),30),(mv("e2e4"),10)]);
+    assert_eq!(book.pick(&start,BookChoice::Best,0),Some(mv("d2d4")));
+
+    let mut castles:Vec<Move> = book.moves(&castle).iter().map(|entry| entry.mv).collect();
+    castles.sort_by_key(|mv| mv.to_string());
+    assert_eq!(castles,vec![mv("e1c1"),mv("e1g1")]);
+
+    let after = Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+    assert!(book.moves(&after).is_empty());
+    assert_eq!(book.pick(&after,BookChoice::Weighted,7),None);
+}
+
+#[test]
+fn weighted_pick_follows_weights(){
+    let start = Position::startpos();
+    let book = book(vec![
+        entry(start.zobrist(),"e2","e4",0,1),
+        entry(start.zobrist(),"d2","d4",0,3),
+    ]);
+    let d4 = (0..1000u64).filter(|seed| book.pick(&start,BookChoice::Weighted,*seed) == Some(mv("d2d4"))).count();
+    assert!((650..850).contains(&d4),"d4 picked {} times out of 1000",d4);
+}
+
+#[test]
+fn rejects_broken_files(){
+    assert!(Book::from_bytes(&[0;15]).is_err());
+    let start = Position::startpos();
+    let unsorted = [entry(start.zobrist(),"e2","e4",0,1),entry(1,"e2","e4",0,1)].concat();
+    assert!(Book::from_bytes(&unsorted).is_err());
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+use chess::board::{Color, Position};
+use chess::clock::{Bonus, Clock, TimeControl};
+
+fn secs(seconds:u64)->Duration{
+    Duration::from_secs(seconds)
+}
+
+fn clock(minutes:u64,bonus:u64,kind:Bonus)->Clock{
+    Clock::new(TimeControl::new(secs(minutes*60),secs(bonus),kind))
+}
+
+#[test]
+fn waits_for_the_first_move(){
+    let mut clock = clock(5,0,Bonus::Fischer);
+    clock.tick(secs(10));
+    assert_eq!(clock.remaining(Color::White),secs(300));
+    assert_eq!(clock.press(Color::White),secs(300));
+    assert_eq!(clock.running(),Some(Color::Black));
+    clock.tick(secs(10));
+    assert_eq!(clock.remaining(Color::Black),secs(290));
+}
+
+#[test]
+fn fischer_adds_the_increment(){
+    let mut clock = clock(3,2,Bonus::Fischer);
+    clock.start(Color::White);
+    clock.tick(secs(5));
+    assert_eq!(clock.press(Color::White),secs(177));
+    clock.tick(secs(1));
+    assert_eq!(clock.press(Color::Black),secs(181));
+}
+
+#[test]
+fn bronstein_gives_back_the_time_used(){
+    let mut clock = clock(3,2,Bonus::Bronstein);
+    clock.start(Color::White);
+    clock.tick(secs(5));
+    assert_eq!(clock.press(Color::White),secs(177));
+    clock.tick(secs(1));
+    assert_eq!(clock.press(Color::Black),secs(180));
+}
+
+#[test]
+fn delay_holds_the_clock(){
+    let mut clock = clock(3,2,Bonus::Delay);
+    clock.start(Color::White);
+    clock.tick(secs(1));
+    assert_eq!(clock.delay_left(),secs(1));
+    assert_eq!(clock.remaining(Color::White),secs(180));
+    clock.tick(secs(4));
+    assert_eq!(clock.delay_left(),Duration::ZERO);
+    assert_eq!(clock.press(Color::White),secs(177));
+    //a fresh delay for black
+    clock.tick(secs(2));
+    assert_eq!(clock.remaining(Color::Black),secs(180));
+}
+
+#[test]
+fn flag_falls(){
+    let mut clock = clock(1,5,Bonus::Fischer);
+    clock.start(Color::White);
+    clock.tick(secs(59));
+    assert_eq!(clock.flagged(),None);
+    clock.tick(secs(2));
+    assert_eq!(clock.flagged(),Some(Color::White));
+    //no increment brings it back
+    assert_eq!(clock.press(Color::White),Duration::ZERO);
+    assert_eq!(clock.flagged(),Some(Color::White));
+}
+
+#[test]
+fn time_control_names(){
+    assert_eq!(TimeControl::new(secs(300),secs(3),Bonus::Fischer).to_string(),"5+3");
+    assert_eq!(TimeControl::new(secs(90),secs(0),Bonus::Delay).to_string(),"1:30+0 delay");
+    assert_eq!(TimeControl::new(secs(300),secs(3),Bonus::Fischer).pgn_tag(),Some("300+3".to_string()));
+    assert_eq!(TimeControl::new(secs(600),secs(0),Bonus::Bronstein).pgn_tag(),Some("600".to_string()));
+    assert_eq!(TimeControl::new(secs(600),secs(5),Bonus::Delay).pgn_tag(),None);
+}
+
+#[test]
+fn mating_material(){
+    let material = |fen:&str,color:Color| Position::from_fen(fen).unwrap().has_mating_material(color);
+    //a lone king never, a pawn or a rook always
+    assert!(!material("8/8/4k3/8/8/8/4K3/7r w - - 0 1",Color::White));
+    assert!(material("8/8/4k3/8/8/8/4K3/7r w - - 0 1",Color::Black));
+    assert!(material("8/8/4k3/8/8/8/3PK3/7r w - - 0 1",Color::White));
+    //a knight against a bare king cannot, against a rook the rook may block its own king
+    assert!(!material("8/8/4k3/8/8/8/3NK3/8 w - - 0 1",Color::White));
+    assert!(material("8/8/4k3/8/8/8/3NK3/7r w - - 0 1",Color::White));
+    assert!(material("8/8/4k3/8/8/8/2NNK3/8 w - - 0 1",Color::White));
+    assert!(material("8/8/4k3/8/8/8/2BNK3/8 w - - 0 1",Color::White));
+    //bishops on one square color need something on the other color to box the king in
+    assert!(!material("8/8/4k3/8/8/8/4K3/B1B5 w - - 0 1",Color::White));
+    assert!(!material("8/8/4k3/8/8/8/4K3/B1B3b1 w - - 0 1",Color::White));
+    assert!(material("8/8/4k3/8/8/8/4K3/B1B4b w - - 0 1",Color::White));
+    assert!(material("8/8/4k3/8/8/8/4K3/B1B4n w - - 0 1",Color::White));
+    assert!(material("8/8/4k3/8/8/8/4K3/BB6 w - - 0 1",Color::White));
+}
@@ -0,0 +1,85 @@
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+use chess::board::{Move, Position};
+use chess::engine::{Engine, EngineConfig, EngineLimits};
+
+//the scripted engine from src/bin/fake_uci.rs
+fn fake(args:&[&str])->EngineConfig{
+    EngineConfig{
+        path:env!("CARGO_BIN_EXE_fake_uci").to_string(),
+        args:args.iter().map(|arg| arg.to_string()).collect(),
+        options:Vec::new(),
+    }
+}
+
+fn think(engine:&mut Engine,moves:&[Move])->Result<Move,String>{
+    let limits = EngineLimits::MoveTime(Duration::from_millis(100));
+    engine.best_move(&Position::startpos(),moves,limits,&AtomicBool::new(false))
+}
+
+#[test]
+fn reads_the_config(){
+    let config = EngineConfig::parse("# comment\npath = /usr/bin/stockfish\nargs = -a -b\n\noption Skill Level = 10\n").unwrap();
+    assert_eq!(config.path,"/usr/bin/stockfish");
+    assert_eq!(config.args,vec!["-a","-b"]);
+    assert_eq!(config.options,vec![("Skill Level".to_string(),"10".to_string())]);
+    assert!(EngineConfig::parse("args = -a").is_err());
+    assert!(EngineConfig::parse("path = x\nspeed = 3").is_err());
+}
+
+#[test]
+fn plays_a_move(){
+    let mut config = fake(&[]);
+    config.options.push(("Skill Level".to_string(),"3".to_string()));
+    let mut engine = Engine::start(&config).unwrap();
+    assert_eq!(engine.name,"fake engine");
+    assert_eq!(engine.options.len(),3);
+    assert_eq!(engine.options[0].name,"Hash");
+    assert_eq!(engine.options[0].default.as_deref(),Some("16"));
+
+    let first = think(&mut engine,&[]).unwrap();
+    assert!(Position::startpos().is_legal(&first));
+    let second = think(&mut engine,&[first]).unwrap();
+    let mut position = Position::startpos();
+    position.play(&first);
+    assert!(position.is_legal(&second));
+    engine.new_game().unwrap();
+    assert!(engine.is_alive());
+}
+
+#[test]
+fn unknown_options(){
+    let mut config = fake(&[]);
+    config.options.push(("Threads".to_string(),"2".to_string()));
+    let error = Engine::start(&config).err().unwrap();
+    assert!(error.contains("no option 'Threads'"),"{}",error);
+}
+
+#[test]
+fn missing_executable(){
+    let config = EngineConfig{path:"no/such/engine".to_string(),args:Vec::new(),options:Vec::new()};
+    assert!(Engine::start(&config).is_err());
+}
+
+#[test]
+fn crashes(){
+    let mut engine = Engine::start(&fake(&["--crash-after","1"])).unwrap();
+    let first = think(&mut engine,&[]).unwrap();
+    let error = think(&mut engine,&[first]).err().unwrap();
+    assert!(error.contains("exited") || error.contains("stopped talking"),"{}",error);
+    assert!(!engine.is_alive());
+}
+
+#[test]
+fn hangs(){
+    let mut engine = Engine::start(&fake(&["--hang"])).unwrap();
+    let error = think(&mut engine,&[]).err().unwrap();
+    assert!(error.contains("did not answer in time"),"{}",error);
+}
+
+#[test]
+fn illegal_moves(){
+    let mut engine = Engine::start(&fake(&["--illegal"])).unwrap();
+    let error = think(&mut engine,&[]).err().unwrap();
+    assert!(error.contains("illegal move 'a1a1'"),"{}",error);
+}
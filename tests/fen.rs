@@ -0,0 +1,76 @@
+use chess::board::{Color, PieceKind, Position, Square};
+use chess::fen::STARTPOS;
+
+#[test]
+fn startpos(){
+    let position = Position::from_fen(STARTPOS).unwrap();
+    assert_eq!(position,Position::startpos());
+    assert_eq!(position.to_fen(),STARTPOS);
+}
+
+#[test]
+fn round_trip(){
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 13 57",
+        "4k3/8/8/8/8/8/8/4K2R w K - 99 120",
+    ]{
+        assert_eq!(Position::from_fen(fen).unwrap().to_fen(),fen);
+    }
+}
+
+#[test]
+fn fields(){
+    let position = Position::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 7 42").unwrap();
+    assert_eq!(position.side_to_move(),Color::White);
+    assert_eq!(position.en_passant(),Square::from_name("d6"));
+    assert_eq!(position.halfmove_clock(),7);
+    assert_eq!(position.fullmove_number(),42);
+    assert_eq!(position.piece_at(Square::from_name("e5").unwrap()).map(|piece| piece.kind),Some(PieceKind::Pawn));
+    //the clocks may be left out
+    let short = Position::from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+    assert_eq!(short.to_fen(),"4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+}
+
+#[test]
+fn rejects_broken_fens(){
+    for fen in [
+        "",
+        "4k3/8/8/8/8/8/8/4K3",
+        "4k3/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K4 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K2 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4X3 w - - 0 1",
+        "8/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w X - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+    ]{
+        assert!(Position::from_fen(fen).is_err(),"{}",fen);
+    }
+}
+
+#[test]
+fn rejects_impossible_positions(){
+    for fen in [
+        //black is in check with white to move
+        "4k3/4R3/8/8/8/8/8/4K3 w - - 0 1",
+        //pawns on the first and last rank
+        "4k2P/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/p3K3 w - - 0 1",
+        //en passant squares without a pawn that just went two squares
+        "4k3/8/8/8/8/8/8/4K3 w - e6 0 1",
+        "4k3/8/8/8/4p3/8/8/4K3 w - e6 0 1",
+        "4k3/4p3/8/4p3/8/8/8/4K3 w - e6 0 1",
+        "4k3/8/8/4p3/8/8/8/4K3 b - e6 0 1",
+        "4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - e4 0 1",
+    ]{
+        assert!(Position::from_fen(fen).is_err(),"{}",fen);
+    }
+    //the side to move may be in check
+    assert!(Position::from_fen("4k3/4R3/8/8/8/8/8/4K3 b - - 0 1").is_ok());
+}
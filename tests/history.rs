@@ -0,0 +1,197 @@
+use chess::board::{Move, Piece, PieceKind, Position, Square};
+use chess::history::History;
+
+//the app's side of it without bevy: pieces with an id that does not survive
+//a capture, moved the way play_move_script and undo_redo_script move them
+#[derive(Clone,Copy,Debug,PartialEq)]
+struct Shown{
+    id:u32,
+    piece:Piece,
+    sq:Square,
+    move_count:u32,
+}
+
+struct Screen{
+    pieces:Vec<Shown>,
+    next_id:u32,
+    position:Position,
+    history:History<Shown>,
+    //the moves on the board, like the PGN record
+    record:Vec<Move>,
+}
+
+impl Screen{
+    fn new(position:Position)->Screen{
+        let mut screen = Screen{pieces:Vec::new(),next_id:0,position,history:History::new(),record:Vec::new()};
+        for (sq,piece) in position.pieces(){
+            screen.spawn(Shown{id:0,piece,sq,move_count:0});
+        }
+        screen
+    }
+
+    //every spawn is a new id, like a new entity
+    fn spawn(&mut self,shown:Shown){
+        self.pieces.push(Shown{id:self.next_id,..shown});
+        self.next_id+=1;
+    }
+
+    fn at(&mut self,sq:Square)->Option<&mut Shown>{
+        self.pieces.iter_mut().find(|shown| shown.sq == sq)
+    }
+
+    fn play(&mut self,san:&str){
+        let mv = self.position.parse_san(san).unwrap();
+        self.play_move(mv);
+    }
+
+    fn play_move(&mut self,mv:Move){
+        let captured = self.position.captured_square(&mv).and_then(|sq| {
+            let index = self.pieces.iter().position(|shown| shown.sq == sq)?;
+            Some(self.pieces.remove(index))
+        });
+        if let Some((from,to)) = self.position.castling_rook(&mv){
+            let rook = self.at(from).unwrap();
+            rook.sq = to;
+            rook.move_count+=1;
+        }
+        let mover = self.at(mv.from).unwrap();
+        mover.sq = mv.to;
+        mover.move_count+=1;
+        if let Some(kind) = mv.promotion{
+            mover.piece.kind = kind;
+        }
+        self.history.push(&self.position,mv,captured);
+        self.position.play(&mv);
+        self.record.push(mv);
+        self.check();
+    }
+
+    fn undo(&mut self){
+        let mv = self.record.pop().unwrap();
+        let taken_back = self.history.take_back(mv).unwrap();
+        assert_eq!(taken_back.mv,mv);
+        let mover = self.at(mv.to).unwrap();
+        mover.sq = mv.from;
+        mover.move_count-=1;
+        if mv.promotion.is_some(){
+            mover.piece.kind = PieceKind::Pawn;
+        }
+        if let Some((from,to)) = taken_back.rook{
+            let rook = self.at(from).unwrap();
+            rook.sq = to;
+            rook.move_count-=1;
+        }
+        if let Some((shown,sq)) = taken_back.captured{
+            assert_eq!(shown.sq,sq);
+            self.spawn(shown);
+        }
+        self.position = taken_back.before;
+        self.check();
+    }
+
+    fn redo(&mut self){
+        let mv = self.history.redo().unwrap();
+        self.play_move(mv);
+    }
+
+    //the pieces on screen are the position's, one on each square
+    fn check(&self){
+        let mut shown:Vec<(Square,Piece)> = self.pieces.iter().map(|shown| (shown.sq,shown.piece)).collect();
+        let mut expected:Vec<(Square,Piece)> = self.position.pieces().collect();
+        shown.sort_by_key(|(sq,_)| sq.index());
+        expected.sort_by_key(|(sq,_)| sq.index());
+        assert_eq!(shown,expected);
+    }
+
+    fn shown(&self,sq:&str)->Shown{
+        let sq = Square::from_name(sq).unwrap();
+        *self.pieces.iter().find(|shown| shown.sq == sq).unwrap()
+    }
+}
+
+fn after(moves:&[&str])->Position{
+    let mut position = Position::startpos();
+    for san in moves{
+        let mv = position.parse_san(san).unwrap();
+        position.play(&mv);
+    }
+    position
+}
+
+#[test]
+fn takes_back_across_a_capture(){
+    let mut screen = Screen::new(Position::startpos());
+    for san in ["Nf3","d5","e3","Bg4","Be2","Bxf3"]{
+        screen.play(san);
+    }
+    let knight = screen.shown("f3");
+    //the knight comes back as a new piece and the bishop still finds its way home
+    screen.undo();
+    assert_ne!(screen.shown("f3").id,knight.id);
+    assert_eq!(screen.shown("f3").move_count,1);
+    screen.undo();
+    screen.undo();
+    assert_eq!(screen.position,after(&["Nf3","d5","e3"]));
+    for _ in 0..3{
+        screen.redo();
+    }
+    screen.undo();
+    screen.undo();
+    screen.redo();
+    assert_eq!(screen.shown("f3").move_count,1);
+    screen.redo();
+    assert_eq!(screen.shown("f3").move_count,2);
+    assert_eq!(screen.history.redo(),None);
+    while !screen.record.is_empty(){
+        screen.undo();
+    }
+    assert_eq!(screen.position,Position::startpos());
+}
+
+#[test]
+fn takes_back_castling_en_passant_and_promotion(){
+    let mut screen = Screen::new(Position::from_fen("r3k2r/1P6/8/8/4p3/8/3P4/R3K2R w KQkq - 0 1").unwrap());
+    for san in ["d4","exd3","O-O-O","O-O","bxa8=Q"]{
+        screen.play(san);
+    }
+    for _ in 0..5{
+        screen.undo();
+    }
+    for _ in 0..5{
+        screen.redo();
+    }
+    assert_eq!(screen.shown("d1").move_count,1);
+    assert_eq!(screen.shown("g8").move_count,1);
+    assert_eq!(screen.shown("a8").piece.kind,PieceKind::Queen);
+    for _ in 0..5{
+        screen.undo();
+    }
+    assert_eq!(screen.pieces.len(),9);
+    assert!(screen.pieces.iter().all(|shown| shown.move_count == 0));
+}
+
+#[test]
+fn a_new_move_drops_the_redo_line(){
+    let mut screen = Screen::new(Position::startpos());
+    screen.play("e4");
+    screen.play("e5");
+    screen.undo();
+    assert_eq!(screen.history.redo(),Some(Move::from_uci("e7e5").unwrap()));
+    //the same move again keeps it going
+    screen.redo();
+    screen.undo();
+    screen.undo();
+    screen.play("e4");
+    assert_eq!(screen.history.redo(),Some(Move::from_uci("e7e5").unwrap()));
+    screen.play("c5");
+    assert_eq!(screen.history.redo(),None);
+}
+
+#[test]
+fn moves_from_before_the_history_have_nothing_to_take_back(){
+    let mut history:History<()> = History::new();
+    let e4 = Move::from_uci("e2e4").unwrap();
+    assert_eq!(history.take_back(e4),None);
+    //it can still be played again
+    assert_eq!(history.redo(),Some(e4));
+}
@@ -0,0 +1,72 @@
+use chess::board::Position;
+use chess::perft::perft;
+
+//reference positions and node counts from https://www.chessprogramming.org/Perft_Results;
+//the deeper counts take a while in a debug build, run them with `cargo test -- --ignored`
+
+const KIWIPETE:&str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3:&str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4:&str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_5:&str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const POSITION_6:&str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+fn check(fen:&str,counts:&[u64]){
+    let position = Position::from_fen(fen).unwrap();
+    for (depth,expected) in counts.iter().enumerate(){
+        assert_eq!(perft(&position,depth as u32+1),*expected,"{} at depth {}",fen,depth+1);
+    }
+}
+
+#[test]
+fn startpos(){
+    check(chess::fen::STARTPOS,&[20,400,8902,197281]);
+}
+
+#[test]
+fn kiwipete(){
+    check(KIWIPETE,&[48,2039,97862]);
+}
+
+#[test]
+fn position_3(){
+    check(POSITION_3,&[14,191,2812,43238]);
+}
+
+#[test]
+fn position_4(){
+    check(POSITION_4,&[6,264,9467]);
+}
+
+#[test]
+fn position_5(){
+    check(POSITION_5,&[44,1486,62379]);
+}
+
+#[test]
+fn position_6(){
+    check(POSITION_6,&[46,2079,89890]);
+}
+
+#[test]
+#[ignore]
+fn deep(){
+    let deep = [
+        (chess::fen::STARTPOS,5,4865609),
+        (KIWIPETE,4,4085603),
+        (POSITION_3,5,674624),
+        (POSITION_4,4,422333),
+        (POSITION_5,4,2103487),
+        (POSITION_6,4,3894594),
+    ];
+    for (fen,depth,expected) in deep{
+        assert_eq!(perft(&Position::from_fen(fen).unwrap(),depth),expected,"{} at depth {}",fen,depth);
+    }
+}
+
+#[test]
+fn divide_adds_up(){
+    let position = Position::from_fen(KIWIPETE).unwrap();
+    let divide = chess::perft::divide(&position,2);
+    assert_eq!(divide.len(),48);
+    assert_eq!(divide.iter().map(|(_,nodes)| nodes).sum::<u64>(),2039);
+}
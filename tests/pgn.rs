@@ -0,0 +1,117 @@
+use std::time::Duration;
+use chess::board::Position;
+use chess::pgn::{parse_pgn, PgnGame};
+
+const TWO_GAMES:&str = r#"[Event "Club championship"]
+[Site "?"]
+[Date "2023.01.07"]
+[Round "1"]
+[White "Anderssen"]
+[Black "Kieseritzky"]
+[Result "1-0"]
+
+1. e4 e5 2. f4 {the King's Gambit} exf4 3. Bc4 $6 Qh4+ 4. Kf1 b5!? (4... Nf6
+5. Nc3 (5. d3) c6) 5. Bxb5 ; a rest of line comment
+1-0
+
+[Event "Blitz"]
+[White "A"]
+[Black "B"]
+[Result "1/2-1/2"]
+
+1.d4 d5 2.c4 {[%clk 0:04:58]} e6 {[%clk 0:04:55] solid} 1/2-1/2
+"#;
+
+fn play(position:&mut Position,moves:&[&str])->Vec<chess::board::Move>{
+    moves.iter().map(|san| {
+        let mv = position.parse_san(san).unwrap();
+        position.play(&mv);
+        mv
+    }).collect()
+}
+
+#[test]
+fn reads_every_game(){
+    let games = parse_pgn(TWO_GAMES).unwrap();
+    assert_eq!(games.len(),2);
+    assert_eq!(games[0].tag("White"),Some("Anderssen"));
+    assert_eq!(games[0].result(),"1-0");
+    assert_eq!(games[0].moves.len(),9);
+    assert_eq!(games[1].tag("Event"),Some("Blitz"));
+    assert_eq!(games[1].result(),"1/2-1/2");
+    assert_eq!(games[1].moves.len(),4);
+}
+
+#[test]
+fn keeps_comments_and_nags(){
+    let games = parse_pgn(TWO_GAMES).unwrap();
+    let moves = &games[0].moves;
+    assert_eq!(moves[2].comment.as_deref(),Some("the King's Gambit"));
+    //"$6" written out and "!?" stuck to the move
+    assert_eq!(moves[4].nags,vec![6]);
+    assert_eq!(moves[7].nags,vec![5]);
+    assert_eq!(moves[8].comment.as_deref(),Some("a rest of line comment"));
+}
+
+#[test]
+fn skips_variations(){
+    let game = &parse_pgn(TWO_GAMES).unwrap()[0];
+    let mut position = Position::startpos();
+    let expected = play(&mut position,&["e4","e5","f4","exf4","Bc4","Qh4+","Kf1","b5","Bxb5"]);
+    let played:Vec<_> = game.moves.iter().map(|record| record.mv).collect();
+    assert_eq!(played,expected);
+    assert!(parse_pgn("1. e4 e5 2. Nf3 ) Nc6 *").is_err());
+}
+
+#[test]
+fn reads_clocks(){
+    let moves = &parse_pgn(TWO_GAMES).unwrap()[1].moves;
+    assert_eq!(moves[2].clock,Some(Duration::from_secs(298)));
+    assert_eq!(moves[2].comment,None);
+    assert_eq!(moves[3].clock,Some(Duration::from_secs(295)));
+    assert_eq!(moves[3].comment.as_deref(),Some("solid"));
+}
+
+#[test]
+fn ignores_bad_clocks(){
+    for reading in ["-1:00:00","inf","NaN","0:0x:10","1e300"]{
+        let text = format!("1. e4 {{[%clk {}]}} *",reading);
+        let games = parse_pgn(&text).unwrap();
+        let record = &games[0].moves[0];
+        assert_eq!(record.clock,None,"{}",reading);
+        assert!(record.comment.as_deref().unwrap_or("").contains(reading),"{}",reading);
+    }
+}
+
+#[test]
+fn export_round_trip(){
+    //missing roster tags come back as "?", after that nothing changes
+    for game in parse_pgn(TWO_GAMES).unwrap(){
+        let text = game.to_pgn();
+        let again = parse_pgn(&text).unwrap();
+        assert_eq!(again.len(),1);
+        assert_eq!(again[0].moves,game.moves);
+        assert_eq!(again[0].to_pgn(),text);
+    }
+}
+
+#[test]
+fn round_trip_from_a_set_up_position(){
+    let start = Position::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R b KQkq - 0 30").unwrap();
+    let mut game = PgnGame::new(start);
+    let mut position = start;
+    for (i,mv) in play(&mut position,&["O-O","bxa8=Q","Rxa8","O-O-O"]).into_iter().enumerate(){
+        game.push(mv,Some(Duration::from_secs(60-i as u64)));
+    }
+    game.set_result("*");
+    let text = game.to_pgn();
+    assert!(text.contains("30... O-O {[%clk 0:01:00]} 31. bxa8=Q"),"{}",text);
+    assert!(text.contains("[FEN \"r3k2r/1P6/8/8/8/8/8/R3K2R b KQkq - 0 30\"]"),"{}",text);
+    assert_eq!(parse_pgn(&text).unwrap(),vec![game]);
+}
+
+#[test]
+fn rejects_illegal_moves(){
+    assert!(parse_pgn("1. e4 e5 2. Ke3 *").is_err());
+    assert!(parse_pgn("1. e4 {never closed").is_err());
+}
@@ -0,0 +1,77 @@
+use chess::board::{Move, Position};
+
+fn position(fen:&str)->Position{
+    Position::from_fen(fen).unwrap()
+}
+
+fn uci(text:&str)->Move{
+    Move::from_uci(text).unwrap()
+}
+
+#[test]
+fn writes_moves(){
+    let start = Position::startpos();
+    assert_eq!(start.san(&uci("e2e4")),"e4");
+    assert_eq!(start.san(&uci("g1f3")),"Nf3");
+    //castling, en passant and a promotion that checks
+    let kiwipete = position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    assert_eq!(kiwipete.san(&uci("e1g1")),"O-O");
+    assert_eq!(kiwipete.san(&uci("e1c1")),"O-O-O");
+    assert_eq!(kiwipete.san(&uci("e2a6")),"Bxa6");
+    assert_eq!(position("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").san(&uci("e5d6")),"exd6");
+    assert_eq!(position("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1").san(&uci("b7b8q")),"b8=Q");
+    assert_eq!(position("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").san(&uci("b7b8q")),"b8=Q+");
+    //back rank mate
+    assert_eq!(position("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1").san(&uci("a1a8")),"Ra8#");
+}
+
+#[test]
+fn disambiguates(){
+    //knights on b1 and f3 can both go to d2
+    let files = position("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1");
+    assert_eq!(files.san(&uci("b1d2")),"Nbd2");
+    //rooks on a1 and a5 share the file
+    let ranks = position("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1");
+    assert_eq!(ranks.san(&uci("a1a3")),"R1a3");
+    //queens on e4 and h1 share the file and the rank of the one on h4
+    let squares = position("6k1/8/8/8/4Q2Q/8/1K6/7Q w - - 0 1");
+    assert_eq!(squares.san(&uci("h4e1")),"Qh4e1");
+}
+
+#[test]
+fn reads_moves(){
+    let kiwipete = position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    assert_eq!(kiwipete.parse_san("O-O").unwrap(),uci("e1g1"));
+    assert_eq!(kiwipete.parse_san("0-0-0").unwrap(),uci("e1c1"));
+    assert_eq!(kiwipete.parse_san("Bxa6!?").unwrap(),uci("e2a6"));
+    assert_eq!(kiwipete.parse_san("Qxf6").unwrap(),uci("f3f6"));
+    let promotion = position("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(promotion.parse_san("b8=N").unwrap(),uci("b7b8n"));
+    assert_eq!(promotion.parse_san("b8Q+").unwrap(),uci("b7b8q"));
+}
+
+#[test]
+fn rejects_bad_moves(){
+    let files = position("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1");
+    assert!(files.parse_san("Nd2").unwrap_err().contains("ambiguous"));
+    assert!(files.parse_san("Nd3").unwrap_err().contains("illegal"));
+    assert!(files.parse_san("Xd2").is_err());
+    assert!(files.parse_san("N").is_err());
+    assert!(Position::startpos().parse_san("O-O").is_err());
+}
+
+#[test]
+fn round_trip(){
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "6k1/8/8/8/4Q2Q/8/1K6/7Q w - - 0 1",
+    ]{
+        let position = position(fen);
+        for mv in position.legal_moves(){
+            let san = position.san(&mv);
+            assert_eq!(position.parse_san(&san),Ok(mv),"{} in {}",san,fen);
+        }
+    }
+}
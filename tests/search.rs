@@ -0,0 +1,109 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use chess::board::{Move, Position};
+use chess::search::{self, Limits, MATE};
+
+fn position(fen:&str)->Position{
+    Position::from_fen(fen).unwrap()
+}
+
+fn uci(text:&str)->Move{
+    Move::from_uci(text).unwrap()
+}
+
+fn best(position:&Position,depth:u32)->search::SearchInfo{
+    search::search(position,Limits::depth(depth),&AtomicBool::new(false))
+}
+
+#[test]
+fn finds_mate_in_one(){
+    let back_rank = position("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    let info = best(&back_rank,3);
+    assert_eq!(info.best_move(),Some(uci("a1a8")));
+    assert_eq!(info.mate_in(),Some(1));
+    assert_eq!(info.score,MATE-1);
+}
+
+#[test]
+fn finds_mate_in_two(){
+    //1.Kc7 Ka7 2.Ra1# or 1.Kb6 Kb8 2.Rh8#, the rook check first lets the king out
+    let start = position("k7/8/2K5/8/8/8/8/7R w - - 0 1");
+    let info = best(&start,5);
+    assert_eq!(info.mate_in(),Some(2));
+    let mut after = start;
+    after.play(&info.best_move().unwrap());
+    //whatever black does, the next move mates
+    for reply in after.legal_moves(){
+        let mut next = after;
+        next.play(&reply);
+        let mate = best(&next,3).best_move().unwrap();
+        next.play(&mate);
+        assert!(next.is_checkmate(),"{} after {}",start.san(&info.best_move().unwrap()),after.san(&reply));
+    }
+    //and the side getting mated knows
+    assert_eq!(best(&after,4).mate_in(),Some(-1));
+}
+
+#[test]
+fn takes_hanging_material(){
+    let queen = position("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1");
+    assert_eq!(best(&queen,3).best_move(),Some(uci("d1d5")));
+    //the free pawn, not the knight the e6 pawn covers
+    let defended = position("4k3/8/4p3/3n3p/8/8/8/3QK3 w - - 0 1");
+    assert_eq!(best(&defended,4).best_move(),Some(uci("d1h5")));
+}
+
+#[test]
+fn every_level_plays_a_legal_move(){
+    let positions = [
+        Position::startpos(),
+        position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+        position("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+    ];
+    for (name,depth,millis) in search::LEVELS{
+        for start in &positions{
+            let limits = Limits{depth,movetime:Some(Duration::from_millis(millis)),nodes:None};
+            let info = search::search(start,limits,&AtomicBool::new(false));
+            let mv = info.best_move().unwrap();
+            assert!(start.is_legal(&mv),"{} played {:?} in {}",name,mv,start.to_fen());
+        }
+    }
+}
+
+#[test]
+fn stops_when_told(){
+    //already stopped: the first legal move rather than nothing
+    let start = Position::startpos();
+    let stopped = AtomicBool::new(true);
+    let info = search::search(&start,Limits::depth(64),&stopped);
+    assert!(start.is_legal(&info.best_move().unwrap()));
+
+    //stopped from another thread partway through a search that would run for ages
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = stop.clone();
+    let timer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        thread_stop.store(true,Ordering::Relaxed);
+    });
+    let begin = Instant::now();
+    let info = search::search(&start,Limits::depth(64),&stop);
+    timer.join().unwrap();
+    assert!(begin.elapsed() < Duration::from_secs(5),"took {:?}",begin.elapsed());
+    assert!(start.is_legal(&info.best_move().unwrap()));
+}
+
+#[test]
+fn nothing_to_play_when_the_game_is_over(){
+    //fool's mate
+    let mated = position("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+    let info = best(&mated,3);
+    assert_eq!(info.best_move(),None);
+    assert_eq!(info.score,-MATE);
+
+    let stalemate = position("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+    let info = best(&stalemate,3);
+    assert_eq!(info.best_move(),None);
+    assert_eq!(info.score,0);
+}
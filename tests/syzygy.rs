@@ -0,0 +1,240 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use chess::board::{Move, Piece, PieceKind, Position, Square};
+use chess::syzygy::{Tablebase, Wdl};
+
+#[path = "syzygy/tables.rs"]
+mod tables;
+
+//the smallest valid tables: KQvK with a single value for each side to move
+//(flag 0x80), enough to go through file lookup, color flips and the capture
+//search without real compressed data
+
+fn kqvk_wdl(white_to_move:u8,black_to_move:u8)->Vec<u8>{
+    let mut data = vec![0x71,0xe8,0x23,0x5d];
+    //two sides, no pawns
+    data.push(0x01);
+    //group order, the pieces (king, king, queen) for both sides, padding
+    data.extend_from_slice(&[0x00,0x66,0xee,0x55,0x00]);
+    data.extend_from_slice(&[0x80,white_to_move,0x80,black_to_move]);
+    data
+}
+
+//white to move only, in moves rather than plies
+fn kqvk_dtz(moves:u8)->Vec<u8>{
+    let mut data = vec![0xd7,0x66,0x0c,0xa5];
+    data.push(0x00);
+    data.extend_from_slice(&[0x00,0x66,0xee,0x55,0x00]);
+    data.extend_from_slice(&[0x80,moves]);
+    data
+}
+
+fn directory(name:&str,files:&[(&str,Vec<u8>)])->PathBuf{
+    let directory = std::env::temp_dir().join(format!("chess-syzygy-{}-{}",name,std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    for (file,data) in files{
+        std::fs::write(directory.join(file),data).unwrap();
+    }
+    directory
+}
+
+fn kqvk()->Tablebase{
+    //stored as 0 (loss) to 4 (win)
+    let directory = directory("kqvk",&[("KQvK.rtbw",kqvk_wdl(4,0)),("KQvK.rtbz",kqvk_dtz(5))]);
+    Tablebase::open(directory.to_str().unwrap()).unwrap()
+}
+
+fn position(fen:&str)->Position{
+    Position::from_fen(fen).unwrap()
+}
+
+#[test]
+fn needs_tables(){
+    let empty = directory("empty",&[]);
+    assert!(Tablebase::open(empty.to_str().unwrap()).is_err());
+    assert!(Tablebase::open("no/such/directory").is_err());
+}
+
+#[test]
+fn wdl_from_both_sides(){
+    let tablebase = kqvk();
+    assert_eq!(tablebase.max_pieces(),3);
+    assert_eq!(tablebase.probe_wdl(&position("8/8/8/3k4/8/8/8/1Q2K3 w - - 0 1")),Some(Wdl::Win));
+    assert_eq!(tablebase.probe_wdl(&position("8/8/8/3k4/8/8/8/1Q2K3 b - - 0 1")),Some(Wdl::Loss));
+    //black has the queen: same table with colors swapped
+    assert_eq!(tablebase.probe_wdl(&position("1q2k3/8/8/8/3K4/8/8/8 b - - 0 1")),Some(Wdl::Win));
+    assert_eq!(tablebase.probe_wdl(&position("1q2k3/8/8/8/3K4/8/8/8 w - - 0 1")),Some(Wdl::Loss));
+}
+
+#[test]
+fn captures_come_first(){
+    let tablebase = kqvk();
+    //the table says lost, but the king takes the queen
+    assert_eq!(tablebase.probe_wdl(&position("8/8/8/3k4/3Q4/8/8/4K3 b - - 0 1")),Some(Wdl::Draw));
+    assert_eq!(tablebase.probe_dtz(&position("8/8/8/3k4/3Q4/8/8/4K3 b - - 0 1")),Some(0));
+}
+
+#[test]
+fn dtz_in_plies(){
+    let tablebase = kqvk();
+    assert_eq!(tablebase.probe_dtz(&position("8/8/8/3k4/8/8/8/1Q2K3 w - - 0 1")),Some(11));
+    //only white to move is stored, black's moves all lead to 11
+    assert_eq!(tablebase.probe_dtz(&position("8/8/8/3k4/8/8/8/1Q2K3 b - - 0 1")),Some(-12));
+}
+
+#[test]
+fn outside_the_tables(){
+    let tablebase = kqvk();
+    assert_eq!(tablebase.probe_wdl(&Position::startpos()),None);
+    assert_eq!(tablebase.probe_wdl(&position("8/8/8/3k4/8/8/8/1R2K3 w - - 0 1")),None);
+}
+
+#[test]
+fn best_moves_keep_the_win(){
+    let tablebase = kqvk();
+    let start = position("8/8/8/3k4/8/8/8/1Q2K3 w - - 0 1");
+    let moves = tablebase.best_moves(&start).unwrap();
+    assert!(!moves.is_empty());
+    for mv in moves{
+        let mut next = start;
+        next.play(&mv);
+        assert_eq!(tablebase.probe_wdl(&next),Some(Wdl::Loss),"{}",mv);
+    }
+}
+
+//compressed 3 piece tables from tables.rs, written once for all the tests
+struct ThreePieces{
+    directory:PathBuf,
+    tablebase:Tablebase,
+    solutions:Vec<tables::Solution>,
+}
+
+fn three_pieces()->&'static ThreePieces{
+    static TABLES:OnceLock<ThreePieces> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let directory = directory("three",&[]);
+        let solutions = tables::write_tables(&directory);
+        let tablebase = Tablebase::open(directory.to_str().unwrap()).unwrap();
+        ThreePieces{directory,tablebase,solutions}
+    })
+}
+
+//results any set of 3 piece tables has to give
+fn known_results(tablebase:&Tablebase){
+    let wdl = |fen:&str| tablebase.probe_wdl(&position(fen));
+    let dtz = |fen:&str| tablebase.probe_dtz(&position(fen));
+    //mate in one with the queen or the rook, the rook's being the only one
+    assert_eq!(wdl("k7/8/1K6/8/8/8/7Q/8 w - - 0 1"),Some(Wdl::Win));
+    assert_eq!(dtz("k7/8/1K6/8/8/8/7Q/8 w - - 0 1"),Some(1));
+    let rook_mate = position("6k1/8/6K1/8/8/8/8/R7 w - - 0 1");
+    assert_eq!(tablebase.probe_dtz(&rook_mate),Some(1));
+    assert_eq!(tablebase.best_moves(&rook_mate),Some(vec![Move::from_uci("a1a8").unwrap()]));
+    assert_eq!(wdl("6k1/8/6K1/8/8/8/8/R7 b - - 0 1"),Some(Wdl::Loss));
+    //stalemate, and a rook left hanging
+    assert_eq!(wdl("k7/8/1Q6/8/8/8/8/7K b - - 0 1"),Some(Wdl::Draw));
+    assert_eq!(wdl("8/8/8/8/8/2k5/1R6/7K b - - 0 1"),Some(Wdl::Draw));
+    assert_eq!(dtz("8/8/8/8/8/2k5/1R6/7K b - - 0 1"),Some(0));
+    //the same with colors swapped
+    assert_eq!(wdl("1r5k/2K5/8/8/8/8/8/8 w - - 0 1"),Some(Wdl::Draw));
+    assert_eq!(wdl("8/8/8/3K4/8/8/8/r3k3 w - - 0 1"),Some(Wdl::Loss));
+    //a lone minor piece
+    assert_eq!(wdl("8/8/8/3k4/8/8/8/B3K3 w - - 0 1"),Some(Wdl::Draw));
+    assert_eq!(wdl("8/8/8/3k4/8/8/8/N3K3 b - - 0 1"),Some(Wdl::Draw));
+    //king and pawn: the pawn queens, the defender takes it or is stalemated,
+    //a rook pawn with the king in the corner, and a king move before queening
+    assert_eq!(dtz("8/4P3/8/8/8/8/k7/4K3 w - - 0 1"),Some(1));
+    assert_eq!(wdl("8/8/8/8/8/8/3Pk3/7K b - - 0 1"),Some(Wdl::Draw));
+    assert_eq!(wdl("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"),Some(Wdl::Draw));
+    assert_eq!(wdl("k7/8/8/8/8/8/P7/K7 w - - 0 1"),Some(Wdl::Draw));
+    assert_eq!(wdl("4k3/4P3/4K3/8/8/8/8/8 w - - 0 1"),Some(Wdl::Win));
+    assert_eq!(dtz("4k3/4P3/4K3/8/8/8/8/8 w - - 0 1"),Some(5));
+    //black stronger: stalemated, or winning with the move
+    assert_eq!(wdl("8/8/8/8/8/4k3/4p3/4K3 w - - 0 1"),Some(Wdl::Draw));
+    assert_eq!(wdl("8/8/8/8/8/4k3/4p3/4K3 b - - 0 1"),Some(Wdl::Win));
+}
+
+#[test]
+fn three_piece_tables(){
+    known_results(&three_pieces().tablebase);
+}
+
+//checks a sample of the solved positions against the tables, as they are
+//and with colors swapped
+#[test]
+fn tables_agree_with_the_solve(){
+    let ThreePieces{tablebase,solutions,..} = three_pieces();
+    //the longest wins: mate in 10 with the queen and in 16 with the rook
+    let longest = |kind:PieceKind| solutions.iter().find(|solution| solution.kind == kind).and_then(|solution| solution.dtz.iter().max().copied());
+    assert_eq!(longest(PieceKind::Queen),Some(19));
+    assert_eq!(longest(PieceKind::Rook),Some(31));
+    for solution in solutions{
+        for n in (0..tables::NODES).step_by(37){
+            let Some(position) = tables::position(solution.kind,n) else {continue};
+            let dtz = solution.dtz[n];
+            let wdl = match dtz.signum(){
+                1 => Wdl::Win,
+                0 => Wdl::Draw,
+                _ => Wdl::Loss,
+            };
+            for position in [position,swap_colors(&position)]{
+                let fen = position.to_fen();
+                assert_eq!(tablebase.probe_wdl(&position),Some(wdl),"{}",fen);
+                assert_eq!(tablebase.probe_dtz(&position),Some(dtz),"{}",fen);
+            }
+        }
+    }
+}
+
+fn swap_colors(position:&Position)->Position{
+    let mut swapped = Position::empty();
+    for (sq,piece) in position.pieces(){
+        let sq = Square::from_index(sq.index()^56);
+        swapped.set_piece(sq,Some(Piece::new(piece.kind,piece.color.opposite())));
+    }
+    swapped.set_side_to_move(position.side_to_move().opposite());
+    swapped
+}
+
+//broken headers make a table unreadable, they do not take the program down
+#[test]
+fn corrupt_tables(){
+    let three = three_pieces();
+    let fens = ["6k1/8/6K1/8/8/8/8/R7 w - - 0 1","8/8/8/3k4/8/8/8/R3K3 b - - 0 1"];
+    let original = std::fs::read(three.directory.join("KRvK.rtbw")).unwrap();
+    assert_eq!(three.tablebase.probe_wdl(&position(fens[0])),Some(Wdl::Win));
+    let directory = directory("corrupt",&[]);
+    let mut broken = Vec::new();
+    //every header byte, then the file cut short
+    for at in 0..96.min(original.len()){
+        for byte in [0x00,0xff,original[at]^0x5a]{
+            let mut data = original.clone();
+            data[at] = byte;
+            broken.push(data);
+        }
+    }
+    for len in [0,4,5,9,20,64,original.len()/2,original.len()-1]{
+        broken.push(original[..len].to_vec());
+    }
+    for data in broken{
+        std::fs::write(directory.join("KRvK.rtbw"),&data).unwrap();
+        let tablebase = Tablebase::open(directory.to_str().unwrap()).unwrap();
+        for fen in fens{
+            let _ = tablebase.probe_wdl(&position(fen));
+        }
+    }
+    //pieces the name does not have
+    let mut data = original.clone();
+    data[6] = 0x33;
+    std::fs::write(directory.join("KRvK.rtbw"),&data).unwrap();
+    let tablebase = Tablebase::open(directory.to_str().unwrap()).unwrap();
+    assert_eq!(tablebase.probe_wdl(&position(fens[0])),None);
+}
+
+//the real thing: point SYZYGY_PATH at the official 3 piece tables and run
+//with `cargo test -- --ignored`
+#[test]
+#[ignore]
+fn official_tables(){
+    let path = std::env::var("SYZYGY_PATH").expect("SYZYGY_PATH is not set");
+    known_results(&Tablebase::open(&path).unwrap());
+}
@@ -0,0 +1,668 @@
+//the 3 piece tables (KQvK, KRvK, KBvK, KNvK, KPvK) written the way the
+//Syzygy generator lays out its files: blocks of Huffman coded, recursively
+//paired symbols behind a sparse index, pawn tables split by file and DTZ
+//values through maps. The values come from a retrograde solve with the
+//library's move generator; the indexing is worked out again here, from the
+//format rather than from src/syzygy.rs, so the two have to agree.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::path::Path;
+use chess::board::{Color, Piece, PieceKind, Position, Square};
+
+const WDL_MAGIC:[u8;4] = [0x71,0xe8,0x23,0x5d];
+const DTZ_MAGIC:[u8;4] = [0xd7,0x66,0x0c,0xa5];
+
+//DTZ flags
+const BLACK_TO_MOVE:u8 = 0x01;
+const MAPPED:u8 = 0x02;
+const LOSS_PLIES:u8 = 0x08;
+const WIDE_MAP:u8 = 0x10;
+const SINGLE_VALUE:u8 = 0x80;
+
+//white king, a white piece and black king by square, and the side to move
+pub const NODES:usize = 64*64*64*2;
+
+fn node(white_king:usize,piece:usize,black_king:usize,black_to_move:bool)->usize{
+    ((white_king*64+piece)*64+black_king)*2+black_to_move as usize
+}
+
+fn squares(node:usize)->(usize,usize,usize,bool){
+    (node/2/64/64,node/2/64%64,node/2%64,node%2 == 1)
+}
+
+//None when the pieces overlap, a pawn is on the first or last rank or the
+//side not to move is in check
+pub fn position(kind:PieceKind,node:usize)->Option<Position>{
+    let (white_king,piece,black_king,black_to_move) = squares(node);
+    if white_king == piece || piece == black_king || white_king == black_king{
+        return None
+    }
+    if kind == PieceKind::Pawn && !(8..56).contains(&piece){
+        return None
+    }
+    let mut position = Position::empty();
+    position.set_piece(Square::from_index(white_king),Some(Piece::new(PieceKind::King,Color::White)));
+    position.set_piece(Square::from_index(piece),Some(Piece::new(kind,Color::White)));
+    position.set_piece(Square::from_index(black_king),Some(Piece::new(PieceKind::King,Color::Black)));
+    position.set_side_to_move(if black_to_move {Color::Black} else {Color::White});
+    if position.is_in_check(position.side_to_move().opposite()){
+        return None
+    }
+    Some(position)
+}
+
+//the solve of one material: the DTZ in plies with best play, positive when
+//the side to move wins, negative when it loses and 0 for draws and for
+//nodes that are no position
+pub struct Solution{
+    pub kind:PieceKind,
+    pub dtz:Vec<i32>,
+}
+
+#[derive(Clone,Copy)]
+enum Edge{
+    //a move to a position of the same material, and whether it was a pawn move
+    Next(u32,bool),
+    //the result (-1, 0, 1) for the side to move after a capture or promotion
+    Outcome(i8),
+}
+
+//`promoted` has the solves of the pieces a pawn may become, the others draw
+pub fn solve(kind:PieceKind,promoted:&[&Solution])->Solution{
+    let mut first = vec![0u32;NODES+1];
+    let mut edges = Vec::new();
+    let mut mated = vec![false;NODES];
+    let mut stalemate = vec![false;NODES];
+    for n in 0..NODES{
+        first[n] = edges.len() as u32;
+        let Some(position) = position(kind,n) else {continue};
+        let moves = position.legal_moves();
+        mated[n] = moves.is_empty() && position.is_check();
+        stalemate[n] = moves.is_empty() && !position.is_check();
+        let (white_king,piece,black_king,black_to_move) = squares(n);
+        for mv in moves{
+            let (from,to) = (mv.from.index(),mv.to.index());
+            //only the black king can take anything, and then it is a draw
+            if to == piece{
+                edges.push(Edge::Outcome(0));
+                continue
+            }
+            let moved = |sq:usize| if sq == from {to} else {sq};
+            let child = node(moved(white_king),moved(piece),moved(black_king),!black_to_move);
+            edges.push(match mv.promotion{
+                Some(to) => {
+                    let result = promoted.iter().find(|solution| solution.kind == to).map_or(0,|solution| solution.dtz[child].signum());
+                    Edge::Outcome(result as i8)
+                }
+                None => Edge::Next(child as u32,kind == PieceKind::Pawn && from == piece),
+            });
+        }
+    }
+    first[NODES] = edges.len() as u32;
+    let graph = Graph{first,edges,mated,stalemate};
+    let results = graph.layers(None);
+    let dtz = if kind == PieceKind::Pawn {graph.layers(Some(&results))} else {results};
+    Solution{kind,dtz}
+}
+
+struct Graph{
+    //edges of node n are first[n]..first[n+1]
+    first:Vec<u32>,
+    edges:Vec<Edge>,
+    mated:Vec<bool>,
+    stalemate:Vec<bool>,
+}
+
+impl Graph{
+    //retrograde by distance: the nodes decided in round t are t plies from
+    //the end, which is a mate, a capture or a promotion, and with `zeroed`
+    //(the results with pawn moves followed) also a pawn move
+    fn layers(&self,zeroed:Option<&[i32]>)->Vec<i32>{
+        let mut value:Vec<i32> = self.mated.iter().map(|mated| -(*mated as i32)).collect();
+        let mut decided:Vec<bool> = (0..NODES).map(|n| self.mated[n] || self.stalemate[n]).collect();
+        let mut pending:Vec<usize> = (0..NODES).filter(|n| !decided[*n] && self.first[*n] < self.first[n+1]).collect();
+        loop{
+            let mut found = Vec::new();
+            for n in pending.iter().copied(){
+                let moves = &self.edges[self.first[n] as usize..self.first[n+1] as usize];
+                let mut win:Option<i32> = None;
+                let mut loss = Some(0);
+                for edge in moves{
+                    //the result after the move and how far its end is, if known yet
+                    let known = match *edge{
+                        Edge::Outcome(result) => Some((result as i32,0)),
+                        Edge::Next(child,_) if self.mated[child as usize] => Some((-1,0)),
+                        Edge::Next(child,true) if zeroed.is_some() => Some((zeroed.unwrap()[child as usize].signum(),0)),
+                        Edge::Next(child,_) if decided[child as usize] => {
+                            let value = value[child as usize];
+                            Some((value.signum(),value.abs()))
+                        }
+                        Edge::Next(..) => None,
+                    };
+                    match known{
+                        Some((result,distance)) if result < 0 => win = Some(win.map_or(distance+1,|win| win.min(distance+1))),
+                        Some((result,distance)) if result > 0 => loss = loss.map(|loss| loss.max(distance+1)),
+                        _ => loss = None,
+                    }
+                }
+                if let Some(win) = win{
+                    found.push((n,win));
+                }
+                else if let Some(loss) = loss{
+                    found.push((n,-loss));
+                }
+            }
+            if found.is_empty(){
+                return value
+            }
+            for (n,result) in found{
+                value[n] = result;
+                decided[n] = true;
+            }
+            pending.retain(|n| !decided[*n]);
+        }
+    }
+}
+
+//the a1-d1-d4 triangle, below the diagonal first
+fn triangle(sq:usize)->usize{
+    let order = [1,2,3,10,11,19,0,9,18,27];
+    order.iter().position(|corner| *corner == sq).unwrap()
+}
+
+//the 28 squares below the a1-h8 diagonal
+fn below_diagonal(sq:usize)->usize{
+    (0..sq).filter(|earlier| earlier/8 < earlier%8).count()
+}
+
+//sq among the squares the earlier pieces left free
+fn skip(sq:usize,earlier:&[usize])->usize{
+    sq-earlier.iter().filter(|other| **other < sq).count()
+}
+
+//three unique pieces without pawns: the first moved into the a1-d1-d4
+//triangle, the first of them off the long diagonal moved below it; then
+//numbered by how many stay on the diagonal
+fn pawnless_index(mut squares:[usize;3])->usize{
+    if squares[0]%8 > 3{
+        squares.iter_mut().for_each(|sq| *sq^=7);
+    }
+    if squares[0]/8 > 3{
+        squares.iter_mut().for_each(|sq| *sq^=56);
+    }
+    if let Some(i) = (0..3).find(|i| squares[*i]/8 != squares[*i]%8){
+        if squares[i]/8 > squares[i]%8{
+            squares[i..].iter_mut().for_each(|sq| *sq = (*sq%8)*8+*sq/8);
+        }
+    }
+    let [a,b,c] = squares;
+    let below = |sq:usize| sq/8 < sq%8;
+    //the diagonal squares' ranks, counted the same way
+    let rank = |sq:usize,earlier:&[usize]| sq/8-earlier.iter().filter(|other| **other < sq).count();
+    if below(a){
+        (triangle(a)*63+skip(b,&[a]))*62+skip(c,&[a,b])
+    }
+    else if below(b){
+        (6*63+a/8*28+below_diagonal(b))*62+skip(c,&[a,b])
+    }
+    else if below(c){
+        6*63*62+4*28*62+(a/8*7+rank(b,&[a]))*28+below_diagonal(c)
+    }
+    else{
+        6*63*62+4*28*62+4*7*28+(a/8*7+rank(b,&[a]))*6+rank(c,&[a,b])
+    }
+}
+
+//the pawn first, then the kings; the pawn's rank is counted at `order`
+//among the three
+fn pawn_index(mut squares:[usize;3],order:usize)->(usize,usize){
+    if squares[0]%8 > 3{
+        squares.iter_mut().for_each(|sq| *sq^=7);
+    }
+    let [pawn,first,second] = squares;
+    let mut groups = vec![(skip(first,&[pawn]),63),(skip(second,&[pawn,first]),62)];
+    groups.insert(order,(pawn/8-1,6));
+    let (mut index,mut factor) = (0,1);
+    for (digit,size) in groups{
+        index+=digit*factor;
+        factor*=size;
+    }
+    (pawn%8,index)
+}
+
+const PAWNLESS_SIZE:usize = 31332;
+const PAWN_SIZE:usize = 23436;
+
+//table piece codes: 1 pawn ... 6 king, plus 8 for black
+fn code(kind:PieceKind)->u8{
+    match kind{
+        PieceKind::Pawn => 1,
+        PieceKind::Knight => 2,
+        PieceKind::Bishop => 3,
+        PieceKind::Rook => 4,
+        PieceKind::Queen => 5,
+        PieceKind::King => 6,
+    }
+}
+
+//a side to move (and with a pawn, a file) of a table
+struct Part{
+    pieces:[u8;3],
+    order:usize,
+    flags:u8,
+    block_bits:u8,
+    span_bits:u8,
+    //None where no position or one the tables never look at
+    values:Vec<Option<u16>>,
+}
+
+impl Part{
+    fn new(pieces:[u8;3],order:usize,flags:u8,block_bits:u8,span_bits:u8,size:usize)->Part{
+        Part{pieces,order,flags,block_bits,span_bits,values:vec![None;size]}
+    }
+
+    fn set(&mut self,index:usize,value:u16){
+        let old = self.values[index].replace(value);
+        assert!(old.is_none() || old == Some(value),"two positions at index {} differ",index);
+    }
+
+    //the squares of a node in this part's piece order
+    fn squares(&self,n:usize)->[usize;3]{
+        let (white_king,piece,black_king,_) = squares(n);
+        self.pieces.map(|code| match code{
+            6 => white_king,
+            14 => black_king,
+            _ => piece,
+        })
+    }
+}
+
+fn wdl_value(dtz:i32)->u16{
+    (2+2*dtz.signum()) as u16
+}
+
+//plies as stored: less one, or halved for moves
+fn dtz_value(dtz:i32,flags:u8)->u16{
+    let plies = dtz < 0 && flags & LOSS_PLIES != 0;
+    let dtz = dtz.unsigned_abs() as u16;
+    if plies {dtz-1} else {dtz/2}
+}
+
+//KQvK and friends: white to move with the pieces one way round and black
+//to move another; the DTZ table keeps one side
+fn pawnless(solution:&Solution,dtz_side:Color)->(Vec<u8>,Vec<u8>){
+    let piece = code(solution.kind);
+    let mut wdl = [
+        Part::new([piece,6,14],0,0,6,7,PAWNLESS_SIZE),
+        Part::new([14,6,piece],0,0,5,6,PAWNLESS_SIZE),
+    ];
+    let dtz_flags = if dtz_side == Color::Black {BLACK_TO_MOVE | LOSS_PLIES} else {0};
+    let mut dtz = Part::new([6,piece,14],0,dtz_flags,6,8,PAWNLESS_SIZE);
+    for n in 0..NODES{
+        if position(solution.kind,n).is_none(){
+            continue
+        }
+        let side = n%2;
+        let value = solution.dtz[n];
+        let part = &mut wdl[side];
+        let index = pawnless_index(part.squares(n));
+        part.set(index,wdl_value(value));
+        if side == dtz_side.index() && value != 0{
+            let index = pawnless_index(dtz.squares(n));
+            dtz.set(index,dtz_value(value,dtz_flags));
+        }
+    }
+    (write(WDL_MAGIC,false,&[Vec::from(wdl)],None),write(DTZ_MAGIC,false,&[vec![dtz]],None))
+}
+
+//KPvK: four files, the DTZ table keeping white to move on the a and b
+//files and black to move on the others, mapped both ways
+fn pawn(solution:&Solution)->(Vec<u8>,Vec<u8>){
+    let mut wdl:Vec<Vec<Part>> = (0..4).map(|_| vec![
+        Part::new([1,6,14],0,0,6,7,PAWN_SIZE),
+        Part::new([1,14,6],2,0,6,6,PAWN_SIZE),
+    ]).collect();
+    let mut dtz:Vec<Vec<Part>> = (0..4).map(|file| {
+        let flags = if file < 2 {MAPPED} else {BLACK_TO_MOVE | MAPPED | LOSS_PLIES | WIDE_MAP};
+        vec![Part::new([1,14,6],1,flags,5,7,PAWN_SIZE)]
+    }).collect();
+    for n in 0..NODES{
+        if position(solution.kind,n).is_none(){
+            continue
+        }
+        let side = n%2;
+        let value = solution.dtz[n];
+        let part = &wdl[0][side];
+        let (file,index) = pawn_index(part.squares(n),part.order);
+        wdl[file][side].set(index,wdl_value(value));
+        let part = &dtz[0][0];
+        let (file,index) = pawn_index(part.squares(n),part.order);
+        let part = &mut dtz[file][0];
+        if value != 0 && side == (part.flags & BLACK_TO_MOVE) as usize{
+            part.set(index,dtz_value(value,part.flags));
+        }
+    }
+    //the maps: wins, losses, cursed wins and blessed losses; the table keeps
+    //the value's place in its map
+    let mut maps = Vec::new();
+    for file in dtz.iter_mut(){
+        let part = &mut file[0];
+        let mut map:[Vec<u16>;4] = Default::default();
+        let win = part.flags & BLACK_TO_MOVE == 0;
+        let results = &mut map[if win {0} else {1}];
+        results.extend(part.values.iter().flatten());
+        results.sort();
+        results.dedup();
+        for value in part.values.iter_mut().flatten(){
+            *value = results.iter().position(|result| result == value).unwrap() as u16;
+        }
+        maps.push(map);
+    }
+    (write(WDL_MAGIC,true,&wdl,None),write(DTZ_MAGIC,true,&dtz,Some(&maps)))
+}
+
+//a whole file, parts by file and side to move
+fn write(magic:[u8;4],has_pawns:bool,parts:&[Vec<Part>],maps:Option<&[[Vec<u16>;4]]>)->Vec<u8>{
+    let mut data = magic.to_vec();
+    let sides = parts[0].len();
+    data.push((sides == 2) as u8 | (has_pawns as u8) << 1);
+    for file in parts{
+        let nibbles = |value:&dyn Fn(&Part)->u8| file.iter().enumerate().fold(0,|byte,(side,part)| byte | value(part) << (4*side));
+        data.push(nibbles(&|part| part.order as u8));
+        for k in 0..3{
+            data.push(nibbles(&|part| part.pieces[k]));
+        }
+    }
+    align(&mut data,2);
+    let packed:Vec<Vec<Packed>> = parts.iter().map(|file| file.iter().map(pack).collect()).collect();
+    for packed in packed.iter().flatten(){
+        data.extend_from_slice(&packed.sizes);
+    }
+    if let Some(maps) = maps{
+        for (file,map) in parts.iter().zip(maps){
+            let flags = file[0].flags;
+            if flags & MAPPED == 0{
+                continue
+            }
+            if flags & WIDE_MAP != 0{
+                align(&mut data,2);
+                for results in map{
+                    data.extend_from_slice(&(results.len() as u16).to_le_bytes());
+                    results.iter().for_each(|result| data.extend_from_slice(&result.to_le_bytes()));
+                }
+            }
+            else{
+                for results in map{
+                    data.push(results.len() as u8);
+                    data.extend(results.iter().map(|result| *result as u8));
+                }
+            }
+        }
+        align(&mut data,2);
+    }
+    for packed in packed.iter().flatten(){
+        data.extend_from_slice(&packed.sparse_index);
+    }
+    for packed in packed.iter().flatten(){
+        data.extend_from_slice(&packed.block_lengths);
+    }
+    for packed in packed.iter().flatten(){
+        if !packed.blocks.is_empty(){
+            align(&mut data,64);
+        }
+        data.extend_from_slice(&packed.blocks);
+    }
+    data
+}
+
+fn align(data:&mut Vec<u8>,to:usize){
+    while !data.len().is_multiple_of(to){
+        data.push(0);
+    }
+}
+
+//one part compressed: the header bytes, sparse index, block lengths and blocks
+struct Packed{
+    sizes:Vec<u8>,
+    sparse_index:Vec<u8>,
+    block_lengths:Vec<u8>,
+    blocks:Vec<u8>,
+}
+
+//a symbol is a value or a pair of symbols
+#[derive(Clone,Copy)]
+enum Symbol{
+    Value(u16),
+    Pair(usize,usize),
+}
+
+fn pack(part:&Part)->Packed{
+    //values nobody looks at take the commonest value, they compress best
+    let mut counts = BTreeMap::new();
+    for value in part.values.iter().flatten(){
+        *counts.entry(*value).or_insert(0)+=1;
+    }
+    let common = counts.iter().max_by_key(|(value,count)| (**count,Reverse(**value))).map_or(0,|(value,_)| *value);
+    let values:Vec<u16> = part.values.iter().map(|value| value.unwrap_or(common)).collect();
+    if counts.len() <= 1{
+        return Packed{sizes:vec![part.flags | SINGLE_VALUE,common as u8],sparse_index:Vec::new(),block_lengths:Vec::new(),blocks:Vec::new()}
+    }
+
+    //recursive pairing: the commonest neighbours become a new symbol
+    let mut symbols:Vec<Symbol> = counts.keys().map(|value| Symbol::Value(*value)).collect();
+    let mut expands = vec![1;symbols.len()];
+    let mut text:Vec<usize> = values.iter().map(|value| counts.keys().position(|key| key == value).unwrap()).collect();
+    for _ in 0..10{
+        let mut pairs = BTreeMap::new();
+        for neighbours in text.windows(2){
+            *pairs.entry((neighbours[0],neighbours[1])).or_insert(0)+=1;
+        }
+        let Some((&(left,right),&count)) = pairs.iter().max_by_key(|(pair,count)| (**count,Reverse(**pair))) else {break};
+        if count < 16 || expands[left]+expands[right] > 1024{
+            break
+        }
+        symbols.push(Symbol::Pair(left,right));
+        expands.push(expands[left]+expands[right]);
+        let mut paired = Vec::with_capacity(text.len());
+        let mut i = 0;
+        while i < text.len(){
+            if i+1 < text.len() && text[i] == left && text[i+1] == right{
+                paired.push(symbols.len()-1);
+                i+=2;
+            }
+            else{
+                paired.push(text[i]);
+                i+=1;
+            }
+        }
+        text = paired;
+    }
+
+    //Huffman code lengths of the symbols in use
+    let mut frequency = vec![0u64;symbols.len()];
+    text.iter().for_each(|sym| frequency[*sym]+=1);
+    let used:Vec<usize> = (0..symbols.len()).filter(|sym| frequency[*sym] > 0).collect();
+    let mut length = vec![0u8;symbols.len()];
+    let mut heap:BinaryHeap<Reverse<(u64,usize)>> = BinaryHeap::new();
+    //trees by id: the symbols, then the merged ones
+    let mut parent:Vec<usize> = vec![usize::MAX;symbols.len()];
+    for sym in used.iter(){
+        heap.push(Reverse((frequency[*sym],*sym)));
+    }
+    while heap.len() > 1{
+        let Reverse((a_weight,a)) = heap.pop().unwrap();
+        let Reverse((b_weight,b)) = heap.pop().unwrap();
+        parent.push(usize::MAX);
+        let merged = parent.len()-1;
+        parent[a] = merged;
+        parent[b] = merged;
+        heap.push(Reverse((a_weight+b_weight,merged)));
+    }
+    for sym in used.iter(){
+        let mut depth = 0;
+        let mut at = *sym;
+        while parent[at] != usize::MAX{
+            at = parent[at];
+            depth+=1;
+        }
+        length[*sym] = depth.max(1);
+    }
+    let max_len = *used.iter().map(|sym| &length[*sym]).max().unwrap();
+    let min_len = *used.iter().map(|sym| &length[*sym]).min().unwrap();
+    assert!(max_len <= 32);
+
+    //canonical numbering: longer codes get the lower numbers, unused symbols come last
+    let mut order = used.clone();
+    order.sort_by_key(|sym| (Reverse(length[*sym]),*sym));
+    order.extend((0..symbols.len()).filter(|sym| frequency[*sym] == 0));
+    let mut number = vec![0;symbols.len()];
+    for (i,sym) in order.iter().enumerate(){
+        number[*sym] = i;
+    }
+    let count = |len:u8| used.iter().filter(|sym| length[**sym] == len).count() as u64;
+    let longer = |len:u8| used.iter().filter(|sym| length[**sym] > len).count() as u64;
+    //the first code of each length, from the longest (all zeros) up
+    let mut base = vec![0u64;max_len as usize+1];
+    for len in (min_len..max_len).rev(){
+        let next = base[len as usize+1]+count(len+1);
+        assert!(next.is_multiple_of(2));
+        base[len as usize] = next/2;
+    }
+    let code = |sym:usize| {
+        let len = length[sym];
+        (base[len as usize]+number[sym] as u64-longer(len),len)
+    };
+
+    let mut sizes = vec![part.flags,part.block_bits,part.span_bits];
+    let block_size = 1usize << part.block_bits;
+    let span = 1usize << part.span_bits;
+
+    //blocks of whole symbols, at most 65536 values each
+    let mut blocks:Vec<Vec<u8>> = Vec::new();
+    let mut block_values:Vec<usize> = Vec::new();
+    let mut writer = Bits::default();
+    let mut values_in = 0;
+    for sym in text.iter(){
+        let (bits,len) = code(*sym);
+        if writer.len+len as usize > 8*block_size || values_in+expands[*sym] > 65536{
+            blocks.push(writer.finish(block_size));
+            block_values.push(values_in);
+            writer = Bits::default();
+            values_in = 0;
+        }
+        writer.push(bits,len);
+        values_in+=expands[*sym];
+    }
+    blocks.push(writer.finish(block_size));
+    block_values.push(values_in);
+
+    //the sparse index: block and offset of the value halfway into each span,
+    //past the end counted on into padding blocks
+    let total = values.len();
+    let starts:Vec<usize> = block_values.iter().scan(0,|start,values| {
+        let this = *start;
+        *start+=values;
+        Some(this)
+    }).collect();
+    let mut sparse_index = Vec::new();
+    let mut padding = 0;
+    for k in 0..total.div_ceil(span){
+        let index = k*span+span/2;
+        let (block,offset) = if index < total{
+            let block = starts.iter().rposition(|start| *start <= index).unwrap();
+            (block,index-starts[block])
+        }
+        else{
+            padding = padding.max((index-total)/65536+1);
+            (blocks.len()+(index-total)/65536,(index-total)%65536)
+        };
+        sparse_index.extend_from_slice(&(block as u32).to_le_bytes());
+        sparse_index.extend_from_slice(&(offset as u16).to_le_bytes());
+    }
+    let mut block_lengths = Vec::new();
+    for values in block_values.iter(){
+        block_lengths.extend_from_slice(&(*values as u16-1).to_le_bytes());
+    }
+    for _ in 0..padding{
+        block_lengths.extend_from_slice(&0xffffu16.to_le_bytes());
+    }
+
+    sizes.push(padding as u8);
+    sizes.extend_from_slice(&(blocks.len() as u32).to_le_bytes());
+    sizes.push(max_len);
+    sizes.push(min_len);
+    for len in min_len..=max_len{
+        sizes.extend_from_slice(&(longer(len) as u16).to_le_bytes());
+    }
+    sizes.extend_from_slice(&(symbols.len() as u16).to_le_bytes());
+    for sym in order.iter(){
+        let (left,right) = match symbols[*sym]{
+            Symbol::Value(value) => (value as usize,0xfff),
+            Symbol::Pair(left,right) => (number[left],number[right]),
+        };
+        sizes.extend_from_slice(&[left as u8,(left >> 8) as u8 | (right << 4) as u8,(right >> 4) as u8]);
+    }
+    if symbols.len()%2 == 1{
+        sizes.push(0);
+    }
+    Packed{sizes,sparse_index,block_lengths,blocks:blocks.concat()}
+}
+
+//codes written from the top bit down
+#[derive(Default)]
+struct Bits{
+    bytes:Vec<u8>,
+    len:usize,
+}
+
+impl Bits{
+    fn push(&mut self,code:u64,len:u8){
+        for i in (0..len).rev(){
+            if self.len.is_multiple_of(8){
+                self.bytes.push(0);
+            }
+            if code >> i & 1 == 1{
+                *self.bytes.last_mut().unwrap()|=0x80 >> (self.len%8);
+            }
+            self.len+=1;
+        }
+    }
+
+    fn finish(mut self,size:usize)->Vec<u8>{
+        self.bytes.resize(size,0);
+        self.bytes
+    }
+}
+
+//solves the 3 piece endgames and writes their tables into the directory
+pub fn write_tables(directory:&Path)->Vec<Solution>{
+    let queen = solve(PieceKind::Queen,&[]);
+    let rook = solve(PieceKind::Rook,&[]);
+    let pawn_solution = solve(PieceKind::Pawn,&[&queen,&rook]);
+    let mut files = Vec::new();
+    let (wdl,dtz) = pawnless(&queen,Color::White);
+    files.push(("KQvK",wdl,dtz));
+    let (wdl,dtz) = pawnless(&rook,Color::Black);
+    files.push(("KRvK",wdl,dtz));
+    let (wdl,dtz) = pawn(&pawn_solution);
+    files.push(("KPvK",wdl,dtz));
+    //a lone minor piece never wins: single value tables
+    for (name,piece) in [("KBvK",3),("KNvK",2)]{
+        let pieces:[u8;3] = [piece,6,14];
+        let mut wdl = WDL_MAGIC.to_vec();
+        wdl.extend_from_slice(&[0x01,0x00]);
+        wdl.extend_from_slice(&pieces.map(|code| code | code << 4));
+        wdl.extend_from_slice(&[0x00,SINGLE_VALUE,2,SINGLE_VALUE,2]);
+        let mut dtz = DTZ_MAGIC.to_vec();
+        dtz.extend_from_slice(&[0x00,0x00]);
+        dtz.extend_from_slice(&pieces);
+        dtz.extend_from_slice(&[0x00,SINGLE_VALUE,0]);
+        files.push((name,wdl,dtz));
+    }
+    for (name,wdl,dtz) in files{
+        std::fs::write(directory.join(format!("{}.rtbw",name)),wdl).unwrap();
+        std::fs::write(directory.join(format!("{}.rtbz",name)),dtz).unwrap();
+    }
+    vec![queen,rook,pawn_solution]
+}
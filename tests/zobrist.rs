@@ -0,0 +1,56 @@
+use chess::board::Position;
+
+//keys from the Polyglot book format description
+const POLYGLOT_KEYS:[(&str,u64);9] = [
+    ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",0x463b96181691fc9c),
+    ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",0x823c9b50fd114196),
+    ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",0x0756b94461c50fb0),
+    ("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",0x662fafb965db29d4),
+    ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",0x22a48b5a8e47ff78),
+    ("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 1 3",0x652a607ca3f242c1),
+    ("rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 2 4",0x00fdd303c946bdd9),
+    ("rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3",0x3c8123ea7b067637),
+    ("rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 1 4",0x5c3f9b829b279560),
+];
+
+#[test]
+fn polyglot_keys(){
+    for (fen,key) in POLYGLOT_KEYS{
+        assert_eq!(Position::from_fen(fen).unwrap().zobrist(),key,"{}",fen);
+    }
+}
+
+#[test]
+fn played_moves_keep_the_key(){
+    let mut position = Position::startpos();
+    for (i,text) in ["e2e4","d7d5","e4e5","f7f5","e1e2","e8f7"].iter().enumerate(){
+        let mv = position.legal_moves().into_iter().find(|mv| mv.to_string() == *text).unwrap();
+        position.play(&mv);
+        assert_eq!(position.zobrist(),POLYGLOT_KEYS[i+1].1,"after {}",text);
+    }
+}
+
+//the incremental key has to match one built from scratch, whatever was played
+#[test]
+fn incremental_matches_scratch(){
+    fn walk(position:&Position,depth:u32){
+        let mut scratch = Position::empty();
+        for (sq,piece) in position.pieces(){
+            scratch.set_piece(sq,Some(piece));
+        }
+        scratch.set_side_to_move(position.side_to_move());
+        scratch.set_castling(position.castling());
+        scratch.set_en_passant(position.en_passant());
+        assert_eq!(position.zobrist(),scratch.zobrist(),"{}",position.to_fen());
+        if depth == 0{
+            return
+        }
+        for mv in position.legal_moves(){
+            let mut next = *position;
+            next.play(&mv);
+            walk(&next,depth-1);
+        }
+    }
+    walk(&Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap(),2);
+    walk(&Position::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap(),2);
+}